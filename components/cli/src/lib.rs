@@ -14,10 +14,12 @@ pub struct CliArgs {
 pub enum Command {
     /// Build the site.
     Build(BuildArgs),
-    /// Dump site metadata (for debugging purposes).
-    DumpMetadata,
+    /// Dump site metadata.
+    DumpMetadata(DumpMetadataArgs),
     /// Start a development server.
     Serve(ServeArgs),
+    /// Export a syntax highlighting theme as a standalone CSS stylesheet.
+    SyntectToCss(SyntectToCssArgs),
 }
 
 #[derive(clap::Parser)]
@@ -25,6 +27,28 @@ pub struct BuildArgs {
     /// Include draft files in the output.
     #[arg(long = "drafts")]
     pub include_drafts: bool,
+
+    /// Bypass the incremental build cache: re-render every page and wipe the
+    /// output directory first, as if building from scratch.
+    #[arg(long)]
+    pub force: bool,
+}
+
+#[derive(clap::Parser)]
+pub struct DumpMetadataArgs {
+    /// Output format.
+    #[arg(long, default_value = "debug")]
+    pub format: DumpFormat,
+}
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+pub enum DumpFormat {
+    /// Pretty-printed `Debug` output, for humans eyeballing the site's
+    /// metadata during development.
+    Debug,
+    /// Versioned JSON, stable across hinoki releases, for external tooling
+    /// to consume.
+    Json,
 }
 
 #[derive(clap::Parser)]
@@ -37,3 +61,9 @@ pub struct ServeArgs {
     #[arg(long)]
     pub open: bool,
 }
+
+#[derive(clap::Parser)]
+pub struct SyntectToCssArgs {
+    /// Name of the theme to export, as loaded from `sublime_dir`.
+    pub theme: String,
+}
@@ -5,19 +5,26 @@ use std::{
 };
 
 use anyhow::Context as _;
+use base64::{engine::general_purpose::STANDARD, Engine as _};
 use camino::{Utf8Path, Utf8PathBuf};
 use fs_err::{self as fs, File};
 use indexmap::IndexMap;
 use rayon::iter::{IntoParallelRefIterator as _, ParallelIterator as _};
 use serde::Serialize;
+use sha2::{Digest, Sha256, Sha384};
 use tracing::{error, instrument, warn};
 
-use self::file_config::{AssetFileConfig, ProcessContent};
 use crate::{
     build::OutputDirManager, config::Config, frontmatter::parse_frontmatter, metadata::metadata_env,
 };
 
 mod file_config;
+#[cfg(feature = "images")]
+mod image_ops;
+#[cfg(feature = "sass")]
+mod sass;
+
+pub(crate) use self::file_config::{AssetFileConfig, ProcessContent};
 
 pub(crate) struct AssetsProcessor<'c, 's, 'sc> {
     metadata_env: minijinja::Environment<'static>,
@@ -39,11 +46,8 @@ impl<'c: 'sc, 's, 'sc> AssetsProcessor<'c, 's, 'sc> {
         Ok(())
     }
 
-    pub(crate) fn dump(&self) -> anyhow::Result<()> {
-        let metadata = self.process_assets_dir(&self.cx.assets_dir, WriteOutput::No)?;
-        println!("{metadata:#?}");
-
-        Ok(())
+    pub(crate) fn dump(&self) -> anyhow::Result<DirectoryMetadata> {
+        self.process_assets_dir(&self.cx.assets_dir, WriteOutput::No)
     }
 
     fn process_assets_dir(
@@ -110,7 +114,7 @@ impl<'c: 'sc, 's, 'sc> AssetsProcessor<'c, 's, 'sc> {
         let mut input_file = BufReader::new(File::open(&content_path)?);
 
         let frontmatter = parse_frontmatter(&mut input_file)?;
-        let file_meta = self.file_metadata(source_path.clone(), frontmatter)?;
+        let file_meta = self.file_metadata(source_path.clone(), frontmatter, &content_path)?;
 
         if let WriteOutput::Yes = write_output {
             self.render_file(file_meta.clone(), input_file, content_path)?;
@@ -122,12 +126,12 @@ impl<'c: 'sc, 's, 'sc> AssetsProcessor<'c, 's, 'sc> {
     fn file_metadata(
         &self,
         source_path: Utf8PathBuf,
-        frontmatter: AssetFileConfig,
+        mut frontmatter: AssetFileConfig,
+        content_path: &Utf8Path,
     ) -> anyhow::Result<FileMetadata> {
-        // for defaults in
-        // self.ctx.config.file_config_defaults.for_path(&source_path).rev() {
-        //     frontmatter.apply_defaults(defaults);
-        // }
+        for defaults in self.cx.config.asset_file_settings.for_path(&source_path).rev() {
+            frontmatter.apply_defaults(defaults);
+        }
 
         let source_file_stem = source_path.file_stem().expect("path must have a file name");
         let mut metadata_ctx =
@@ -141,7 +145,7 @@ impl<'c: 'sc, 's, 'sc> AssetsProcessor<'c, 's, 'sc> {
         // Make slug available for path templates
         metadata_ctx.slug = Some(&slug);
 
-        let path = match self.expand_metadata_tpl(frontmatter.path, &metadata_ctx)? {
+        let mut path: Utf8PathBuf = match self.expand_metadata_tpl(frontmatter.path, &metadata_ctx)? {
             Some(path) => path
                 .strip_prefix('/')
                 .context("paths in frontmatter and defaults must begin with '/'")?
@@ -149,10 +153,23 @@ impl<'c: 'sc, 's, 'sc> AssetsProcessor<'c, 's, 'sc> {
             None => source_path.clone(),
         };
 
+        let mut extra = frontmatter.extra;
+        maybe_apply_image_op(&mut path, &mut extra, content_path, &frontmatter.process_content)?;
+        maybe_apply_sass_op(&mut path, &frontmatter.process_content);
+
+        let integrity = if frontmatter.fingerprint.unwrap_or(false) {
+            let content = fs::read(content_path)?;
+            path = fingerprinted_path(&path, &content);
+            Some(format!("sha384-{}", STANDARD.encode(Sha384::digest(&content))))
+        } else {
+            None
+        };
+
         Ok(FileMetadata {
             slug,
             path,
-            extra: frontmatter.extra,
+            integrity,
+            extra,
             process_content: frontmatter.process_content,
         })
     }
@@ -217,7 +234,7 @@ impl<'a> AssetsProcessorContext<'a> {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub(crate) struct DirectoryMetadata {
     pub subdirs: BTreeMap<String, DirectoryMetadata>,
     pub files: Vec<FileMetadata>,
@@ -227,6 +244,10 @@ pub(crate) struct DirectoryMetadata {
 pub(crate) struct FileMetadata {
     pub slug: String,
     pub path: Utf8PathBuf,
+    /// Subresource Integrity string (`sha384-<base64>`), set when this asset
+    /// has content fingerprinting enabled. Templates can pass this straight
+    /// through to an `integrity` attribute.
+    pub integrity: Option<String>,
     #[serde(default)]
     pub extra: IndexMap<String, toml::Value>,
 
@@ -236,6 +257,83 @@ pub(crate) struct FileMetadata {
     pub process_content: Option<ProcessContent>,
 }
 
+/// If `process_content` is an image operation, derives the output path
+/// (keyed by a hash of the source bytes and the operation's parameters, so
+/// unchanged inputs don't churn the filename across rebuilds) and records
+/// its public `url` and on-disk `static_path` in `extra`, so templates can
+/// chain further references to it (e.g. building a `srcset`).
+#[cfg(feature = "images")]
+fn maybe_apply_image_op(
+    path: &mut Utf8PathBuf,
+    extra: &mut IndexMap<String, toml::Value>,
+    content_path: &Utf8Path,
+    process_content: &Option<ProcessContent>,
+) -> anyhow::Result<()> {
+    let Some(ProcessContent::Image(op)) = process_content else { return Ok(()) };
+
+    let content = fs::read(content_path)?;
+    let mut hasher = Sha256::new();
+    hasher.update(&content);
+    hasher.update(format!("{op:?}").as_bytes());
+    let hash = format!("{:x}", hasher.finalize());
+    let hash = &hash[..8];
+
+    let ext = op.format.map(image_ops::ImageFormat::extension).unwrap_or_else(|| {
+        path.extension().expect("image assets are expected to have a file extension")
+    });
+    let stem = path.file_stem().expect("path must have a file name");
+    *path = path.with_file_name(format!("{stem}.{hash}.{ext}"));
+
+    extra.insert("url".to_owned(), toml::Value::String(format!("/{path}")));
+    extra.insert("static_path".to_owned(), toml::Value::String(path.to_string()));
+
+    Ok(())
+}
+
+#[cfg(not(feature = "images"))]
+fn maybe_apply_image_op(
+    _path: &mut Utf8PathBuf,
+    _extra: &mut IndexMap<String, toml::Value>,
+    _content_path: &Utf8Path,
+    _process_content: &Option<ProcessContent>,
+) -> anyhow::Result<()> {
+    Ok(())
+}
+
+/// If `process_content` is a Sass compilation, rewrites the output path's
+/// extension to `.css`, regardless of whether the source was `.scss` or
+/// `.sass`.
+#[cfg(feature = "sass")]
+fn maybe_apply_sass_op(path: &mut Utf8PathBuf, process_content: &Option<ProcessContent>) {
+    if matches!(process_content, Some(ProcessContent::Sass(_))) {
+        *path = path.with_extension("css");
+    }
+}
+
+#[cfg(not(feature = "sass"))]
+fn maybe_apply_sass_op(_path: &mut Utf8PathBuf, _process_content: &Option<ProcessContent>) {}
+
+/// Inserts an 8-hex-character SHA-256 digest of `content` before the file's
+/// extension, e.g. `style.css` -> `style.9f86d081.css`.
+///
+/// The digest only depends on file content, never timestamps, so rebuilding
+/// with unchanged content produces the same fingerprinted filename.
+fn fingerprinted_path(path: &Utf8Path, content: &[u8]) -> Utf8PathBuf {
+    let hash = format!("{:x}", Sha256::digest(content));
+    let hash = &hash[..8];
+
+    match path.extension() {
+        Some(ext) => {
+            let stem = path.file_stem().expect("path must have a file name");
+            path.with_file_name(format!("{stem}.{hash}.{ext}"))
+        }
+        None => {
+            let name = path.file_name().expect("path must have a file name");
+            path.with_file_name(format!("{name}.{hash}"))
+        }
+    }
+}
+
 fn render(
     file_meta: FileMetadata,
     mut input_file: BufReader<File>,
@@ -243,6 +341,23 @@ fn render(
     content_path: Utf8PathBuf,
 ) -> anyhow::Result<()> {
     let output_path = ctx.output_path(&file_meta.path, &content_path)?;
+
+    #[cfg(feature = "images")]
+    if let Some(ProcessContent::Image(op)) = &file_meta.process_content {
+        let mut source = Vec::new();
+        input_file.read_to_end(&mut source)?;
+        let output = image_ops::apply(&source, op)?;
+        fs::write(output_path, output)?;
+        return Ok(());
+    }
+
+    #[cfg(feature = "sass")]
+    if let Some(ProcessContent::Sass(op)) = &file_meta.process_content {
+        let css = sass::compile(&content_path, op)?;
+        fs::write(output_path, css)?;
+        return Ok(());
+    }
+
     let mut output_file = BufWriter::new(File::create(output_path)?);
 
     // Don't buffer file contents in memory if no content processing is needed.
@@ -2,7 +2,12 @@ use indexmap::{map::Entry as IndexMapEntry, IndexMap};
 use serde::Deserialize;
 use toml::map::Entry as TomlMapEntry;
 
-#[derive(Default, Deserialize)]
+#[cfg(feature = "images")]
+use super::image_ops::ImageOp;
+#[cfg(feature = "sass")]
+use super::sass::SassOp;
+
+#[derive(Clone, Default, Deserialize)]
 #[serde(deny_unknown_fields)]
 pub(crate) struct AssetFileConfig {
     /// What kind of processing should be done on the content, if any.
@@ -14,6 +19,15 @@ pub(crate) struct AssetFileConfig {
     /// Custom slug for this page, to replace the file basename.
     pub slug: Option<String>,
 
+    /// If set to `true`, rewrite the output filename to include a short hash
+    /// of the asset's content (e.g. `style.9f86d081.css`), and make a
+    /// `sha384-` Subresource Integrity string available on `FileMetadata`.
+    ///
+    /// Since the fingerprint is content-derived, rebuilding with unchanged
+    /// content produces the same filename, so fingerprinted assets can be
+    /// served with a far-future cache lifetime.
+    pub fingerprint: Option<bool>,
+
     /// Arbitrary additional user-defined data.
     #[serde(default)]
     pub extra: IndexMap<String, toml::Value>,
@@ -30,6 +44,9 @@ impl AssetFileConfig {
         if self.slug.is_none() {
             self.slug = defaults.slug.clone();
         }
+        if self.fingerprint.is_none() {
+            self.fingerprint = defaults.fingerprint;
+        }
         apply_extra_defaults(&mut self.extra, &defaults.extra);
     }
 }
@@ -69,7 +86,12 @@ fn apply_inner_extra_defaults(target: &mut toml::Value, source: &toml::Value) {
 #[derive(Clone, Copy, Debug, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub(crate) enum ProcessContent {
-    // CompileSass,
-    // CompileScss,
-    // CompileTypescript,
+    /// Decode, transform (resize/convert format/re-encode), and write out an
+    /// image. See [`image_ops::ImageOp`][super::image_ops::ImageOp].
+    #[cfg(feature = "images")]
+    Image(ImageOp),
+
+    /// Compile Sass/SCSS to CSS. See [`sass::SassOp`][super::sass::SassOp].
+    #[cfg(feature = "sass")]
+    Sass(SassOp),
 }
@@ -0,0 +1,98 @@
+//! Declarative image transformations applied via
+//! `ProcessContent::Image`: resizing, format conversion, and quality
+//! control for lossy formats.
+
+use std::io::Cursor;
+
+use anyhow::Context as _;
+use image::{codecs::jpeg::JpegEncoder, imageops::FilterType};
+use serde::Deserialize;
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct ImageOp {
+    /// Resize the image before re-encoding it.
+    pub resize: Option<ResizeOp>,
+
+    /// Convert to this format. Keeps the source format if unset.
+    pub format: Option<ImageFormat>,
+
+    /// Output quality (0-100), for formats that support lossy encoding.
+    /// Ignored otherwise.
+    pub quality: Option<u8>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct ResizeOp {
+    pub width: u32,
+    pub height: u32,
+    #[serde(default)]
+    pub mode: ResizeMode,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum ResizeMode {
+    /// Scale down to fit entirely within the given box, preserving aspect
+    /// ratio. The result may be smaller than the box in one dimension.
+    #[default]
+    Fit,
+    /// Scale and crop to exactly fill the given box.
+    Fill,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum ImageFormat {
+    WebP,
+    Avif,
+    Jpeg,
+}
+
+impl ImageFormat {
+    /// File extension used for the derived output file.
+    pub(crate) fn extension(self) -> &'static str {
+        match self {
+            ImageFormat::WebP => "webp",
+            ImageFormat::Avif => "avif",
+            ImageFormat::Jpeg => "jpg",
+        }
+    }
+
+    fn to_image_format(self) -> image::ImageFormat {
+        match self {
+            ImageFormat::WebP => image::ImageFormat::WebP,
+            ImageFormat::Avif => image::ImageFormat::Avif,
+            ImageFormat::Jpeg => image::ImageFormat::Jpeg,
+        }
+    }
+}
+
+/// Decodes `source`, applies `op`, and returns the re-encoded bytes.
+pub(crate) fn apply(source: &[u8], op: &ImageOp) -> anyhow::Result<Vec<u8>> {
+    let mut img = image::load_from_memory(source).context("decoding image")?;
+
+    if let Some(resize) = op.resize {
+        img = match resize.mode {
+            ResizeMode::Fit => img.resize(resize.width, resize.height, FilterType::Lanczos3),
+            ResizeMode::Fill => img.resize_to_fill(resize.width, resize.height, FilterType::Lanczos3),
+        };
+    }
+
+    let format = op
+        .format
+        .map(ImageFormat::to_image_format)
+        .map_or_else(|| image::guess_format(source).context("guessing source image format"), Ok)?;
+
+    let mut output = Vec::new();
+    if let (image::ImageFormat::Jpeg, Some(quality)) = (format, op.quality) {
+        JpegEncoder::new_with_quality(Cursor::new(&mut output), quality)
+            .encode_image(&img)
+            .context("encoding JPEG")?;
+    } else {
+        img.write_to(&mut Cursor::new(&mut output), format).context("encoding image")?;
+    }
+
+    Ok(output)
+}
@@ -0,0 +1,42 @@
+//! Sass/SCSS compilation applied via `ProcessContent::Sass`.
+
+use anyhow::Context as _;
+use camino::Utf8Path;
+use serde::Deserialize;
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct SassOp {
+    /// How to format the compiled CSS. Defaults to `compressed`.
+    #[serde(default)]
+    pub style: OutputStyle,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum OutputStyle {
+    /// One selector/declaration per line, indented.
+    Expanded,
+    /// Whitespace stripped as much as possible.
+    #[default]
+    Compressed,
+}
+
+impl OutputStyle {
+    fn to_grass(self) -> grass::OutputStyle {
+        match self {
+            OutputStyle::Expanded => grass::OutputStyle::Expanded,
+            OutputStyle::Compressed => grass::OutputStyle::Compressed,
+        }
+    }
+}
+
+/// Compiles the Sass/SCSS file at `source_path` to CSS.
+///
+/// Compiling from the path rather than from source bytes lets `grass`
+/// resolve `@use`/`@import` of sibling partials relative to it.
+pub(crate) fn compile(source_path: &Utf8Path, op: &SassOp) -> anyhow::Result<Vec<u8>> {
+    let options = grass::Options::default().style(op.style.to_grass());
+    let css = grass::from_path(source_path, &options).context("compiling Sass")?;
+    Ok(css.into_bytes())
+}
@@ -1,8 +1,14 @@
-use std::{io::ErrorKind, process::ExitCode, sync::atomic::Ordering};
+use std::{
+    collections::{HashMap, HashSet},
+    io::ErrorKind,
+    process::ExitCode,
+    sync::{atomic::Ordering, Arc, Mutex},
+};
 
 use bumpalo_herd::Herd;
-use camino::Utf8Path;
+use camino::{Utf8Path, Utf8PathBuf};
 use fs_err as fs;
+use serde::Serialize;
 use tracing::error;
 
 #[cfg(feature = "syntax-highlighting")]
@@ -10,28 +16,54 @@ use crate::content::LazySyntaxHighlighter;
 use crate::{
     assets::{AssetsProcessor, AssetsProcessorContext},
     config::Config,
-    content::{ContentProcessor, ContentProcessorContext},
+    content::{ContentProcessor, ContentProcessorContext, DirectoryMetadata},
     template::{context::GlobalContext, load_templates},
 };
 
+pub(crate) mod cache;
 mod output_dir;
+#[cfg(feature = "precompression")]
+mod precompress;
 
+use self::cache::{BuildCache, IncrementalCache, IncrementalInputs, NewManifest};
 pub(crate) use self::output_dir::OutputDirManager;
 
 pub struct Build {
     config: Config,
     include_drafts: bool,
+    /// Bypass the incremental build cache: every page is re-rendered and the
+    /// output directory is wiped up front, as if no cache existed.
+    force: bool,
     #[cfg(feature = "syntax-highlighting")]
     syntax_highlighter: LazySyntaxHighlighter,
+    /// Maps a content directory to the source paths of pages recorded (in
+    /// the most recent build) as depending on its file listing, via
+    /// `get_file`/`get_files`. Used to restrict a rebuild to the pages
+    /// affected by a change, instead of re-rendering everything.
+    dependents: Arc<Mutex<HashMap<Utf8PathBuf, Vec<Utf8PathBuf>>>>,
+    /// Maps a canonicalized data file path to the source paths of pages
+    /// recorded (in the most recent build) as depending on it via
+    /// `load_data`. Used the same way as `dependents`.
+    data_dependents: Arc<Mutex<HashMap<Utf8PathBuf, Vec<Utf8PathBuf>>>>,
+    /// The content metadata tree produced by the most recent full walk (a
+    /// [`Self::run_reporting_success`] or [`Self::rebuild_changed`] call),
+    /// for [`Self::rebuild_subpath`] to splice a single recomputed
+    /// subdirectory into instead of re-walking everything. `None` before
+    /// the first such walk.
+    content_root: Mutex<Option<DirectoryMetadata>>,
 }
 
 impl Build {
-    pub fn new(config: Config, include_drafts: bool) -> Self {
+    pub fn new(config: Config, include_drafts: bool, force: bool) -> Self {
         Self {
             config,
             include_drafts,
+            force,
             #[cfg(feature = "syntax-highlighting")]
             syntax_highlighter: LazySyntaxHighlighter::default(),
+            dependents: Arc::new(Mutex::new(HashMap::new())),
+            data_dependents: Arc::new(Mutex::new(HashMap::new())),
+            content_root: Mutex::new(None),
         }
     }
 
@@ -40,15 +72,109 @@ impl Build {
     }
 
     pub fn run(&self) -> ExitCode {
+        if self.run_reporting_success() {
+            ExitCode::SUCCESS
+        } else {
+            ExitCode::FAILURE
+        }
+    }
+
+    /// Like [`Build::run`], but returns whether the build succeeded instead
+    /// of an [`ExitCode`]. Used by callers that need to react to build
+    /// failures programmatically, such as the dev server's live-reload.
+    pub fn run_reporting_success(&self) -> bool {
         let output_dir = self.config.output_dir();
-        if let Err(e) = init_output_directory(&output_dir) {
+        let previous_cache =
+            if self.force { BuildCache::default() } else { BuildCache::load(&self.config.cache_dir()) };
+
+        // A from-scratch build (no usable cache, or `--force`) still wipes
+        // the output directory up front, same as before incremental builds
+        // existed. Otherwise, leftover outputs are swept afterwards instead,
+        // once it's known which of them are actually stale.
+        let init_result = if self.force || previous_cache.is_empty() {
+            init_output_directory(&output_dir)
+        } else {
+            fs::create_dir_all(&output_dir).map_err(anyhow::Error::from)
+        };
+        if let Err(e) = init_result {
             error!("failed to initialize output directory: {e:#}");
         }
 
         let output_dir_mgr = OutputDirManager::new(output_dir);
+        let new_manifest = NewManifest::default();
+        let config_hash = cache::config_hash(&self.config.path, &self.config.include_files);
+        let incremental_inputs = (!self.force).then(|| IncrementalInputs {
+            previous: &previous_cache,
+            new_manifest: &new_manifest,
+            config_hash: &config_hash,
+        });
+
+        let (r1, r2) = rayon::join(
+            || self.process_content(&output_dir_mgr, None, incremental_inputs),
+            || self.process_assets(&output_dir_mgr),
+        );
+
+        let success = match (r1, r2) {
+            (Err(e1), Err(e2)) => {
+                error!("{e1:#}");
+                error!("{e2:#}");
+                false
+            }
+            (Ok(_), Err(e)) | (Err(e), Ok(_)) => {
+                error!("{e:#}");
+                false
+            }
+            (Ok(false), Ok(false)) => true,
+            (Ok(_), Ok(_)) => false,
+        };
+
+        #[cfg(feature = "syntax-highlighting")]
+        let success = success
+            && match self.write_syntax_stylesheets(&output_dir_mgr) {
+                Ok(()) => true,
+                Err(e) => {
+                    error!("failed to write syntax highlighting stylesheet: {e:#}");
+                    false
+                }
+            };
+
+        #[cfg(feature = "precompression")]
+        if success {
+            precompress::precompress_output(&output_dir_mgr, &self.config.precompression);
+        }
+
+        if success {
+            if !self.force {
+                sweep_stale_outputs(&previous_cache, &output_dir_mgr);
+            }
+            if let Err(e) = new_manifest.save(&self.config.cache_dir()) {
+                error!("failed to save incremental build cache: {e:#}");
+            }
+        }
+
+        success
+    }
+
+    /// Re-renders only the content files affected by `changed_content_paths`
+    /// (direct changes, plus any page recorded as depending on one of their
+    /// parent directories via `get_file`/`get_files`), plus every page
+    /// recorded as depending on one of `changed_data_paths` via `load_data`,
+    /// reusing the rest of the previous build's output. Used by the dev
+    /// server to avoid a full rebuild on every content-only change.
+    ///
+    /// `changed_content_paths` must be relative to the content dir;
+    /// `changed_data_paths` must be canonicalized, matching how `load_data`
+    /// paths are recorded.
+    pub fn rebuild_changed(
+        &self,
+        changed_content_paths: &HashSet<Utf8PathBuf>,
+        changed_data_paths: &HashSet<Utf8PathBuf>,
+    ) -> bool {
+        let output_dir_mgr = OutputDirManager::new(self.config.output_dir());
+        let render_only = self.affected_content_paths(changed_content_paths, changed_data_paths);
 
         let (r1, r2) = rayon::join(
-            || self.process_content(&output_dir_mgr),
+            || self.process_content(&output_dir_mgr, Some(render_only), None),
             || self.process_assets(&output_dir_mgr),
         );
 
@@ -56,36 +182,193 @@ impl Build {
             (Err(e1), Err(e2)) => {
                 error!("{e1:#}");
                 error!("{e2:#}");
-                ExitCode::FAILURE
+                false
             }
             (Ok(_), Err(e)) | (Err(e), Ok(_)) => {
                 error!("{e:#}");
-                ExitCode::FAILURE
+                false
+            }
+            (Ok(false), Ok(false)) => true,
+            (Ok(_), Ok(_)) => false,
+        }
+    }
+
+    /// Expands `changed` with every page recorded, during the previous
+    /// build, as depending on one of the changed paths' parent directories
+    /// or on one of `changed_data_paths` via `load_data`, then turns the
+    /// result into absolute paths as seen by
+    /// [`ContentProcessor`][crate::content::ContentProcessor].
+    fn affected_content_paths(
+        &self,
+        changed: &HashSet<Utf8PathBuf>,
+        changed_data_paths: &HashSet<Utf8PathBuf>,
+    ) -> HashSet<Utf8PathBuf> {
+        let dependents = self.dependents.lock().unwrap();
+        let content_dir = self.config.content_dir();
+
+        let mut affected = HashSet::new();
+        for path in changed {
+            affected.insert(content_dir.join(path));
+
+            let dir = path.parent().unwrap_or(Utf8Path::new(""));
+            if let Some(dependent_paths) = dependents.get(dir) {
+                affected.extend(dependent_paths.iter().map(|p| content_dir.join(p)));
             }
-            (Ok(false), Ok(false)) => ExitCode::SUCCESS,
-            (Ok(_), Ok(_)) => ExitCode::FAILURE,
         }
+
+        let data_dependents = self.data_dependents.lock().unwrap();
+        for data_path in changed_data_paths {
+            if let Some(dependent_paths) = data_dependents.get(data_path) {
+                affected.extend(dependent_paths.iter().map(|p| content_dir.join(p)));
+            }
+        }
+
+        affected
+    }
+
+    /// Canonicalized paths of every data file recorded (in the most recent
+    /// build) as read via `load_data`, so the watcher can tell a stray data
+    /// file apart from an irrelevant change elsewhere in the project.
+    pub fn data_file_paths(&self) -> HashSet<Utf8PathBuf> {
+        self.data_dependents.lock().unwrap().keys().cloned().collect()
     }
 
-    fn process_content(&self, output_dir_mgr: &OutputDirManager) -> anyhow::Result<bool> {
+    // Always written, not just when `[syntax_highlight] style = "classed"`:
+    // a page can opt into class-based output for itself via the
+    // `syntax_highlight_theme = "css"` frontmatter sentinel, regardless of
+    // the site-wide default style.
+    #[cfg(feature = "syntax-highlighting")]
+    fn write_syntax_stylesheets(&self, output_dir_mgr: &OutputDirManager) -> anyhow::Result<()> {
+        let highlighter = self
+            .syntax_highlighter
+            .get_or_try_init(|| crate::content::SyntaxHighlighter::new(&self.config.sublime_dir()))?;
+        for path in highlighter.write_stylesheets(&output_dir_mgr.output_dir)? {
+            output_dir_mgr.register_output(path);
+        }
+        Ok(())
+    }
+
+    fn process_content(
+        &self,
+        output_dir_mgr: &OutputDirManager,
+        render_only: Option<HashSet<Utf8PathBuf>>,
+        incremental_inputs: Option<IncrementalInputs<'_>>,
+    ) -> anyhow::Result<bool> {
+        if render_only.is_none() {
+            // Full rebuild: drop stale dependency info instead of letting it
+            // grow unboundedly across incremental rebuilds in between.
+            self.dependents.lock().unwrap().clear();
+            self.data_dependents.lock().unwrap().clear();
+        }
+
         let alloc = Herd::new();
-        let template_env = load_templates(&self.config.template_dir(), &alloc)?;
+        let (template_env, template_hashes) = load_templates(&self.config.template_dir(), &alloc)?;
+        let incremental = incremental_inputs.map(|inputs| IncrementalCache {
+            previous: inputs.previous,
+            new_manifest: inputs.new_manifest,
+            config_hash: cache::combined_config_hash(inputs.config_hash, &template_hashes),
+        });
         let cx = ContentProcessorContext::new(
             &self.config,
             self.include_drafts,
             template_env,
             output_dir_mgr,
             GlobalContext::new(
-                #[cfg(feature = "syntax-highlighting")]
-                &self.config,
                 #[cfg(feature = "syntax-highlighting")]
                 self.syntax_highlighter.clone(),
+                #[cfg(feature = "syntax-highlighting")]
+                self.config.sublime_dir(),
+                #[cfg(feature = "syntax-highlighting")]
+                self.config.syntax_highlight.style,
+                #[cfg(feature = "markdown")]
+                self.config.markdown.clone(),
+                #[cfg(feature = "markdown")]
+                self.config.base_url.clone(),
+                self.dependents.clone(),
+                self.data_dependents.clone(),
+                self.config.asset_dir(),
+                self.config.content_dir(),
+                #[cfg(feature = "images")]
+                self.config.output_dir(),
             ),
+            render_only,
+            incremental,
         );
-        rayon::scope(|scope| ContentProcessor::new(scope, &cx).run())?;
+        let root = rayon::scope(|scope| ContentProcessor::new(scope, &cx).run())?;
+        *self.content_root.lock().unwrap() = Some(root);
         Ok(cx.did_error.load(Ordering::Relaxed))
     }
 
+    /// Recomputes a single content directory's metadata (re-rendering its
+    /// own files and any subdirectories beneath it), then splices the
+    /// result back into the content tree built by the last
+    /// [`Self::run_reporting_success`] or [`Self::rebuild_changed`] call,
+    /// instead of re-walking the whole content directory. For a
+    /// file-watcher driving fast, targeted rebuilds.
+    ///
+    /// `changed` is relative to the content dir and may name either a file
+    /// or a directory; a file's parent directory is rebuilt. Falls back to
+    /// a full [`Self::run_reporting_success`] if there's no previous
+    /// content tree to splice into yet.
+    pub fn rebuild_subpath(&self, changed: &Utf8Path) -> anyhow::Result<bool> {
+        let Some(root) = self.content_root.lock().unwrap().clone() else {
+            return Ok(self.run_reporting_success());
+        };
+
+        let content_dir = self.config.content_dir();
+        let target_rel: Utf8PathBuf = if content_dir.join(changed).is_dir() {
+            changed.to_owned()
+        } else {
+            changed.parent().unwrap_or(Utf8Path::new("")).to_owned()
+        };
+        let path_components: Vec<String> =
+            target_rel.as_str().split('/').filter(|s| !s.is_empty()).map(str::to_owned).collect();
+
+        let output_dir_mgr = OutputDirManager::new(self.config.output_dir());
+        let alloc = Herd::new();
+        let (template_env, _template_hashes) = load_templates(&self.config.template_dir(), &alloc)?;
+        let cx = ContentProcessorContext::new(
+            &self.config,
+            self.include_drafts,
+            template_env,
+            &output_dir_mgr,
+            GlobalContext::new(
+                #[cfg(feature = "syntax-highlighting")]
+                self.syntax_highlighter.clone(),
+                #[cfg(feature = "syntax-highlighting")]
+                self.config.sublime_dir(),
+                #[cfg(feature = "syntax-highlighting")]
+                self.config.syntax_highlight.style,
+                #[cfg(feature = "markdown")]
+                self.config.markdown.clone(),
+                #[cfg(feature = "markdown")]
+                self.config.base_url.clone(),
+                self.dependents.clone(),
+                self.data_dependents.clone(),
+                self.config.asset_dir(),
+                self.config.content_dir(),
+                #[cfg(feature = "images")]
+                self.config.output_dir(),
+            ),
+            None,
+            None,
+        );
+
+        let spliced = rayon::scope(|scope| {
+            ContentProcessor::new(scope, &cx).rebuild_subtree(
+                &root,
+                &path_components,
+                &content_dir.join(&target_rel),
+            )
+        })?;
+
+        let success = !cx.did_error.load(Ordering::Relaxed);
+        if success {
+            *self.content_root.lock().unwrap() = Some(spliced);
+        }
+        Ok(success)
+    }
+
     fn process_assets(&self, output_dir_mgr: &OutputDirManager) -> anyhow::Result<bool> {
         let cx = AssetsProcessorContext::new(&self.config, output_dir_mgr);
         rayon::scope(|scope| AssetsProcessor::new(scope, &cx).run())?;
@@ -93,37 +376,132 @@ impl Build {
     }
 }
 
-pub fn build(config: Config, include_drafts: bool) -> ExitCode {
-    Build::new(config, include_drafts).run()
+/// Deletes every output path `previous` recorded that isn't among
+/// `output_dir_mgr`'s current output paths, i.e. whose source content file,
+/// asset, or generated page no longer produces it. Leaves everything else on
+/// disk untouched, which is what makes an incremental build fast: unlike
+/// [`init_output_directory`], this never wipes the directory up front.
+fn sweep_stale_outputs(previous: &BuildCache, output_dir_mgr: &OutputDirManager) {
+    let current = output_dir_mgr.output_paths();
+    for stale_path in previous.all_output_paths().filter(|path| !current.contains(*path)) {
+        remove_file_and_precompressed_siblings(stale_path);
+    }
 }
 
-pub fn dump(config: Config) -> ExitCode {
+/// Removes `path`, plus its `.gz`/`.br` siblings left behind by
+/// `precompress::precompress_output` if there are any, so a stale output
+/// doesn't leave orphaned precompressed copies behind once the sweep above
+/// stops short of wiping the whole output directory.
+fn remove_file_and_precompressed_siblings(path: &Utf8Path) {
+    for candidate in [path.to_owned(), format!("{path}.gz").into(), format!("{path}.br").into()] {
+        if let Err(e) = fs::remove_file(&candidate)
+            && e.kind() != ErrorKind::NotFound
+        {
+            error!("failed to remove stale output `{candidate}`: {e:#}");
+        }
+    }
+}
+
+pub fn build(config: Config, include_drafts: bool, force: bool) -> ExitCode {
+    Build::new(config, include_drafts, force).run()
+}
+
+/// Output mode for [`dump`].
+#[derive(Clone, Copy)]
+pub enum DumpFormat {
+    /// Pretty-printed `Debug` output, for humans eyeballing the site's
+    /// metadata during development.
+    Debug,
+    /// Versioned JSON, stable across hinoki releases, for external tooling
+    /// to consume.
+    Json,
+}
+
+pub fn dump(config: Config, format: DumpFormat) -> ExitCode {
     let output_dir_mgr = OutputDirManager::new("".into());
-    let cx = ContentProcessorContext::new(
+    let content_cx = ContentProcessorContext::new(
         &config,
         true,
         minijinja::Environment::empty(),
         &output_dir_mgr,
         GlobalContext::new(
-            #[cfg(feature = "syntax-highlighting")]
-            &config,
             #[cfg(feature = "syntax-highlighting")]
             LazySyntaxHighlighter::default(),
+            #[cfg(feature = "syntax-highlighting")]
+            config.sublime_dir(),
+            #[cfg(feature = "syntax-highlighting")]
+            config.syntax_highlight.style,
+            #[cfg(feature = "markdown")]
+            config.markdown.clone(),
+            #[cfg(feature = "markdown")]
+            config.base_url.clone(),
+            Arc::new(Mutex::new(HashMap::new())),
+            Arc::new(Mutex::new(HashMap::new())),
+            config.asset_dir(),
+            config.content_dir(),
+            #[cfg(feature = "images")]
+            config.output_dir(),
         ),
+        None,
+        None,
     );
+    let assets_cx = AssetsProcessorContext::new(&config, &output_dir_mgr);
 
-    let res = rayon::scope(|scope| ContentProcessor::new(scope, &cx).dump());
-    assert!(!cx.did_error.load(Ordering::Relaxed));
+    let (content_res, assets_res) = rayon::join(
+        || rayon::scope(|scope| ContentProcessor::new(scope, &content_cx).dump()),
+        || rayon::scope(|scope| AssetsProcessor::new(scope, &assets_cx).dump()),
+    );
+    assert!(!content_cx.did_error.load(Ordering::Relaxed));
+    assert!(!assets_cx.did_error.load(Ordering::Relaxed));
 
-    match res {
-        Ok(_) => ExitCode::SUCCESS,
-        Err(e) => {
+    let (content, assets) = match (content_res, assets_res) {
+        (Ok(content), Ok(assets)) => (content, assets),
+        (Err(e1), Err(e2)) => {
+            error!("{e1:#}");
+            error!("{e2:#}");
+            return ExitCode::FAILURE;
+        }
+        (Err(e), _) | (_, Err(e)) => {
             error!("{e:#}");
-            ExitCode::FAILURE
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match format {
+        DumpFormat::Debug => {
+            println!("{content:#?}");
+            println!("{assets:#?}");
+            ExitCode::SUCCESS
         }
+        DumpFormat::Json => match serde_json::to_string_pretty(&MetadataDumpV1 {
+            version: 1,
+            content,
+            assets,
+        }) {
+            Ok(json) => {
+                println!("{json}");
+                ExitCode::SUCCESS
+            }
+            Err(e) => {
+                error!("{e:#}");
+                ExitCode::FAILURE
+            }
+        },
     }
 }
 
+/// Versioned, machine-readable site map, serialized to JSON by `--format
+/// json`. `version` is bumped whenever the shape of `content`/`assets`
+/// changes incompatibly, so external tooling can tell which shape it's
+/// looking at; a future incompatible change should introduce a sibling
+/// `MetadataDumpV2` rather than altering this one.
+#[derive(Serialize)]
+struct MetadataDumpV1 {
+    version: u32,
+    content: crate::content::DirectoryMetadata,
+    assets: crate::assets::DirectoryMetadata,
+}
+
 fn init_output_directory(output_dir: &Utf8Path) -> anyhow::Result<()> {
     let read_dir = match fs::read_dir(output_dir) {
         Ok(r) => r,
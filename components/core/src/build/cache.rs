@@ -0,0 +1,223 @@
+//! Persisted content-hash cache enabling incremental builds: skip
+//! re-rendering a templated page whose content, every loaded template, and
+//! the relevant `Config` files are all unchanged since the last build that
+//! wrote this cache.
+//!
+//! Lives under `<cache_dir>/manifest.json` (`.hinoki-cache` by default,
+//! configurable via `Config::set_cache_dir`), separate from `output_dir`, so
+//! manually clearing the output directory doesn't leave hinoki thinking a
+//! stale build is current.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use camino::{Utf8Path, Utf8PathBuf};
+use fs_err as fs;
+use itertools::Itertools as _;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+const CACHE_FILE_NAME: &str = "manifest.json";
+
+/// What a page's output depended on, the last time it was rendered.
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct CacheEntry {
+    content_hash: String,
+    /// Hash combining every config file's contents with every loaded
+    /// template's raw source (see [`combined_config_hash`]). Deliberately
+    /// coarse, the same way [`config_hash`] is: hinoki doesn't track which
+    /// partials a given page's template `{% include %}`s or `{% extends %}`,
+    /// so editing any template has to be treated as potentially affecting
+    /// every page, rather than invalidating nothing for pages that don't
+    /// name the edited template directly in frontmatter.
+    config_hash: String,
+    /// Every output path this page wrote, last time it was rendered. Carried
+    /// forward unchanged across skipped renders, so the stale-output sweep
+    /// in [`crate::build::Build::run_reporting_success`] still knows to keep
+    /// them, and so it can tell them apart from outputs whose source has
+    /// since disappeared.
+    output_paths: Vec<Utf8PathBuf>,
+    /// Set (via [`NewManifest::mark_aggregate`]) once this page's render is
+    /// observed to read data beyond its own content file and the templates
+    /// already covered by `config_hash` — through `get_file`/`get_files`,
+    /// `load_data`, `get_taxonomy`/`get_taxonomy_term`, or because the page
+    /// is generated from a `paginate_by`/`repeat` collection. Once true,
+    /// [`BuildCache::is_up_to_date`] always reports stale for this entry:
+    /// hinoki doesn't track exactly which siblings or data files such a page
+    /// reads, so (like `config_hash`) it's cheaper and safer to always
+    /// re-render it than to risk silently serving stale aggregated output.
+    is_aggregate: bool,
+}
+
+impl CacheEntry {
+    pub(crate) fn new(
+        content_hash: String,
+        config_hash: String,
+        output_paths: Vec<Utf8PathBuf>,
+    ) -> Self {
+        Self { content_hash, config_hash, output_paths, is_aggregate: false }
+    }
+
+    pub(crate) fn output_paths(&self) -> &[Utf8PathBuf] {
+        &self.output_paths
+    }
+}
+
+/// Versioned on-disk shape of the manifest, following the same pattern as
+/// [`super::MetadataDumpV1`]: bump the version and introduce a sibling type
+/// rather than changing this one, if the shape ever needs to change
+/// incompatibly.
+#[derive(Default, Serialize, Deserialize)]
+struct ManifestV1 {
+    entries: HashMap<String, CacheEntry>,
+}
+
+/// The previous build's manifest, consulted (read-only) while deciding
+/// whether to skip re-rendering a page.
+#[derive(Default)]
+pub(crate) struct BuildCache {
+    entries: HashMap<String, CacheEntry>,
+}
+
+impl BuildCache {
+    /// Loads the manifest written by the last successful build, or an empty
+    /// cache if there isn't one (first build in `cache_dir`, or it failed to
+    /// parse, e.g. after a hinoki upgrade changed its shape).
+    pub(crate) fn load(cache_dir: &Utf8Path) -> Self {
+        let path = cache_dir.join(CACHE_FILE_NAME);
+        match fs::read_to_string(&path) {
+            Ok(json) => match serde_json::from_str::<ManifestV1>(&json) {
+                Ok(manifest) => Self { entries: manifest.entries },
+                Err(e) => {
+                    warn!("ignoring unreadable build cache at `{path}`: {e:#}");
+                    Self::default()
+                }
+            },
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Self::default(),
+            Err(e) => {
+                warn!("ignoring unreadable build cache at `{path}`: {e:#}");
+                Self::default()
+            }
+        }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Whether `content_rel_path`'s `current` entry has the same content,
+    /// config, and output paths as the last time this file was rendered, and
+    /// that previous render wasn't flagged [`CacheEntry::is_aggregate`]. A
+    /// page that reads aggregated data is never considered up to date: a
+    /// change to whatever sibling or data file it aggregated wouldn't be
+    /// reflected in any of the hashes compared here.
+    pub(crate) fn is_up_to_date(&self, content_rel_path: &Utf8Path, current: &CacheEntry) -> bool {
+        let Some(previous) = self.entries.get(content_rel_path.as_str()) else { return false };
+        !previous.is_aggregate
+            && previous.content_hash == current.content_hash
+            && previous.config_hash == current.config_hash
+            && previous.output_paths == current.output_paths
+    }
+
+    /// Every output path recorded by the last build, across all entries, for
+    /// the stale-output sweep to diff against the current build's output
+    /// paths.
+    pub(crate) fn all_output_paths(&self) -> impl Iterator<Item = &Utf8PathBuf> {
+        self.entries.values().flat_map(|entry| &entry.output_paths)
+    }
+}
+
+/// Accumulates the manifest for the build currently in progress, for
+/// [`BuildCache::load`] to read back on the next one. Pages are recorded
+/// whether or not their render was actually skipped, so a page that was
+/// up-to-date this run stays up-to-date next run too.
+#[derive(Default)]
+pub(crate) struct NewManifest {
+    entries: Mutex<HashMap<String, CacheEntry>>,
+}
+
+impl NewManifest {
+    pub(crate) fn record(&self, content_rel_path: &Utf8Path, entry: CacheEntry) {
+        self.entries.lock().unwrap().insert(content_rel_path.as_str().to_owned(), entry);
+    }
+
+    /// Flags `content_rel_path`'s already-[`record`][Self::record]ed entry
+    /// as having read aggregated data, a no-op if nothing was recorded for
+    /// it. Called once a page's render has actually completed and it's known
+    /// whether it called an aggregating template function, since that can't
+    /// be known up front the way `content_hash`/`config_hash`/`output_paths`
+    /// can.
+    pub(crate) fn mark_aggregate(&self, content_rel_path: &Utf8Path) {
+        if let Some(entry) = self.entries.lock().unwrap().get_mut(content_rel_path.as_str()) {
+            entry.is_aggregate = true;
+        }
+    }
+
+    pub(crate) fn save(&self, cache_dir: &Utf8Path) -> anyhow::Result<()> {
+        fs::create_dir_all(cache_dir)?;
+        let manifest = ManifestV1 { entries: self.entries.lock().unwrap().clone() };
+        let json = serde_json::to_string_pretty(&manifest)?;
+        fs::write(cache_dir.join(CACHE_FILE_NAME), json)?;
+        Ok(())
+    }
+}
+
+/// Hashes `config_path` and every file it (transitively) `include`s, so
+/// editing any of them invalidates every page's cache entry. Deliberately
+/// coarse: hinoki doesn't track which part of `Config` a given page actually
+/// depends on, so any change has to be treated as potentially affecting
+/// every page.
+pub(crate) fn config_hash(config_path: &Utf8Path, include_files: &[Utf8PathBuf]) -> String {
+    let mut hasher = blake3::Hasher::new();
+    for path in std::iter::once(config_path).chain(include_files.iter().map(Utf8PathBuf::as_path)) {
+        if let Ok(bytes) = fs::read(path) {
+            hasher.update(&bytes);
+        } else {
+            // Treat an unreadable config file as changed, rather than
+            // failing the build over it.
+            hasher.update(path.as_str().as_bytes());
+        }
+    }
+    hasher.finalize().to_hex().to_string()
+}
+
+/// Combines `config_hash` (see [`config_hash`]) with every loaded template's
+/// content hash, so that editing any template — not just one a page names
+/// directly in frontmatter — invalidates every page's cache entry. Template
+/// hashes are sorted by path first so the result doesn't depend on
+/// `template_hashes`' iteration order.
+pub(crate) fn combined_config_hash(
+    config_hash: &str,
+    template_hashes: &HashMap<Utf8PathBuf, String>,
+) -> String {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(config_hash.as_bytes());
+    for (path, hash) in template_hashes.iter().sorted_by_key(|(path, _)| path.as_str()) {
+        hasher.update(path.as_str().as_bytes());
+        hasher.update(hash.as_bytes());
+    }
+    hasher.finalize().to_hex().to_string()
+}
+
+/// Raw ingredients for [`IncrementalCache`], threaded into
+/// `Build::process_content` before `load_templates` has produced the
+/// template hashes needed to complete it.
+pub(crate) struct IncrementalInputs<'a> {
+    pub(crate) previous: &'a BuildCache,
+    pub(crate) new_manifest: &'a NewManifest,
+    pub(crate) config_hash: &'a str,
+}
+
+/// Bundles everything [`crate::content::ContentProcessorContext`] needs to
+/// consult and update the incremental build cache while rendering a page.
+/// Not used by `Build::rebuild_changed`'s dev-server incremental rebuilds,
+/// which already have their own in-memory `render_only`-based mechanism for
+/// skipping unaffected pages.
+pub(crate) struct IncrementalCache<'a> {
+    pub(crate) previous: &'a BuildCache,
+    pub(crate) new_manifest: &'a NewManifest,
+    /// [`IncrementalInputs::config_hash`] combined with every loaded
+    /// template's content hash (see [`combined_config_hash`]), so that
+    /// editing any template invalidates every page's cache entry.
+    pub(crate) config_hash: String,
+}
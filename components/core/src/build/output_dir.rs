@@ -30,16 +30,41 @@ impl OutputDirManager {
         Self { output_dir, output_subdirs: Default::default(), output_files: Default::default() }
     }
 
-    pub(crate) fn output_path(
-        &self,
-        output_rel_path: &Utf8Path,
-        source_path: &Utf8Path,
-    ) -> anyhow::Result<Utf8PathBuf> {
+    /// Every output path written so far, for the post-build link checker to
+    /// validate internal links against, and for the incremental build's
+    /// stale-output sweep to keep.
+    pub(crate) fn output_paths(&self) -> HashSet<Utf8PathBuf> {
+        self.output_files.lock().unwrap().keys().cloned().collect()
+    }
+
+    /// Registers `output_path` as current for this build, without creating
+    /// directories or checking for conflicts. For outputs written directly
+    /// (bypassing [`Self::output_path`]), like syntax highlighting
+    /// stylesheets, so the incremental build's stale-output sweep doesn't
+    /// delete them.
+    pub(crate) fn register_output(&self, output_path: Utf8PathBuf) {
+        self.output_files.lock().unwrap().entry(output_path.clone()).or_insert(output_path);
+    }
+
+    /// Resolves `output_rel_path` to its final on-disk path (appending
+    /// `index.html` for a directory-like path), without creating directories
+    /// or registering it. For callers that need to know a page's output path
+    /// ahead of actually writing it, like the incremental build cache
+    /// deciding whether a skipped page's previous outputs are still current.
+    pub(crate) fn resolve_output_path(&self, output_rel_path: &Utf8Path) -> Utf8PathBuf {
         let mut output_path = self.output_dir.join(output_rel_path);
         if output_path.as_str().ends_with("/") {
             output_path.push("index.html");
         }
+        output_path
+    }
 
+    pub(crate) fn output_path(
+        &self,
+        output_rel_path: &Utf8Path,
+        source_path: &Utf8Path,
+    ) -> anyhow::Result<Utf8PathBuf> {
+        let output_path = self.resolve_output_path(output_rel_path);
         let dir = output_path.parent().unwrap();
 
         // This is racy, but that's okay.
@@ -0,0 +1,88 @@
+//! Build-time gzip/brotli precompression of compressible output files,
+//! enabled via the `precompression` feature.
+//!
+//! Writes a `.gz` and `.br` sibling alongside each compressible file, so the
+//! dev server's `ServeDir` (configured with `.precompressed_gzip()` /
+//! `.precompressed_br()`) and static hosts that serve precompressed assets
+//! as-is can skip compressing on every request.
+
+use std::time::SystemTime;
+
+use async_compression::{
+    tokio::write::{BrotliEncoder, GzipEncoder},
+    Level,
+};
+use camino::Utf8Path;
+use rayon::iter::{IntoParallelRefIterator as _, ParallelIterator as _};
+use tokio::io::AsyncWriteExt as _;
+use tracing::error;
+
+use super::OutputDirManager;
+use crate::config::PrecompressionConfig;
+
+/// Precompresses every compressible file produced by the build, in
+/// parallel.
+///
+/// Failures are logged and otherwise ignored: a missing `.gz`/`.br` sibling
+/// just means that file is served uncompressed, not a broken build.
+pub(crate) fn precompress_output(output_dir_mgr: &OutputDirManager, config: &PrecompressionConfig) {
+    let runtime = match tokio::runtime::Builder::new_current_thread().enable_all().build() {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            error!("failed to start precompression runtime: {e:#}");
+            return;
+        }
+    };
+
+    let output_paths = output_dir_mgr.output_paths();
+    output_paths.par_iter().for_each(|output_path| {
+        if !is_compressible(output_path, config) {
+            return;
+        }
+
+        if let Err(e) = runtime.block_on(precompress_file(output_path, config.min_size)) {
+            error!("failed to precompress `{output_path}`: {e:#}");
+        }
+    });
+}
+
+fn is_compressible(path: &Utf8Path, config: &PrecompressionConfig) -> bool {
+    path.extension().is_some_and(|ext| config.extensions.iter().any(|allowed| allowed == ext))
+}
+
+async fn precompress_file(path: &Utf8Path, min_size: u64) -> anyhow::Result<()> {
+    let source_modified = tokio::fs::metadata(path).await?.modified()?;
+
+    // Skip files whose `.gz`/`.br` siblings are already at least as new as
+    // the source: its content didn't change since the last time this file
+    // was compressed.
+    if is_up_to_date(path, "gz", source_modified).await
+        && is_up_to_date(path, "br", source_modified).await
+    {
+        return Ok(());
+    }
+
+    let content = tokio::fs::read(path).await?;
+    if (content.len() as u64) < min_size {
+        return Ok(());
+    }
+
+    let mut gz = GzipEncoder::with_quality(Vec::new(), Level::Best);
+    gz.write_all(&content).await?;
+    gz.shutdown().await?;
+    tokio::fs::write(format!("{path}.gz"), gz.into_inner()).await?;
+
+    let mut br = BrotliEncoder::with_quality(Vec::new(), Level::Best);
+    br.write_all(&content).await?;
+    br.shutdown().await?;
+    tokio::fs::write(format!("{path}.br"), br.into_inner()).await?;
+
+    Ok(())
+}
+
+async fn is_up_to_date(path: &Utf8Path, sibling_ext: &str, source_modified: SystemTime) -> bool {
+    match tokio::fs::metadata(format!("{path}.{sibling_ext}")).await {
+        Ok(meta) => meta.modified().is_ok_and(|modified| modified >= source_modified),
+        Err(_) => false,
+    }
+}
@@ -1,9 +1,15 @@
+use anyhow::Context as _;
 use camino::{Utf8Path, Utf8PathBuf};
+use fs_err as fs;
 use globset::{Glob, GlobSet, GlobSetBuilder};
 use indexmap::{IndexMap, indexmap};
 use serde::{Deserialize, Deserializer, de};
+use toml::map::Entry as TomlMapEntry;
 
-use crate::content::{ContentFileConfig, ProcessContent};
+use crate::{
+    assets::AssetFileConfig,
+    content::{ContentFileConfig, ProcessContent},
+};
 
 #[derive(Clone, Default, Deserialize)]
 #[serde(deny_unknown_fields)]
@@ -18,9 +24,49 @@ pub struct Config {
     sublime_dir: Utf8PathBuf,
     #[serde(default = "default_output_dir")]
     output_dir: Utf8PathBuf,
+    #[serde(default = "default_cache_dir")]
+    cache_dir: Utf8PathBuf,
+
+    /// The site's canonical base URL, used to tell external links apart from
+    /// internal ones.
+    pub base_url: Option<String>,
 
     #[serde(default, rename = "content")]
     pub content_file_settings: ContentFileSettings,
+    #[serde(default, rename = "asset")]
+    pub asset_file_settings: AssetFileSettings,
+    #[serde(default)]
+    pub markdown: MarkdownConfig,
+    #[serde(default)]
+    pub syntax_highlight: SyntaxHighlightConfig,
+    #[serde(default)]
+    pub languages: LanguagesConfig,
+
+    /// How to order a directory's files for template iteration.
+    #[serde(default)]
+    pub sort: SortConfig,
+
+    /// Post-build internal link checking.
+    #[serde(default)]
+    pub link_check: LinkCheckConfig,
+
+    /// Build-time gzip/Brotli precompression of output files, behind the
+    /// `precompression` cargo feature.
+    #[serde(default)]
+    pub precompression: PrecompressionConfig,
+
+    /// Minify rendered HTML output: collapses insignificant whitespace,
+    /// strips comments, and minifies inline `<style>`/`<script>`. Off by
+    /// default.
+    #[serde(default)]
+    pub minify_html: bool,
+
+    /// Names of the taxonomies (e.g. `tags`, `categories`) to collect pages
+    /// into, based on the terms listed in each page's `taxonomies` frontmatter
+    /// field.
+    #[serde(default)]
+    pub taxonomies: Vec<String>,
+
     #[serde(default)]
     pub extra: IndexMap<String, toml::Value>,
 
@@ -29,6 +75,14 @@ pub struct Config {
     /// Populated by [`read_config`][crate::read_config] after deserialization.
     #[serde(skip, default)]
     pub(crate) path: Utf8PathBuf,
+
+    /// Canonicalized paths of every file named (transitively) by an
+    /// `include` key while resolving this config, in the order they were
+    /// read.
+    ///
+    /// Populated by [`read_config`][crate::read_config] alongside `path`.
+    #[serde(skip, default)]
+    pub(crate) include_files: Vec<Utf8PathBuf>,
 }
 
 impl Config {
@@ -52,6 +106,13 @@ impl Config {
         self.project_root().join(&self.output_dir)
     }
 
+    /// Where the incremental build cache's manifest is persisted, separate
+    /// from `output_dir` so clearing the output directory by hand doesn't
+    /// leave a stale cache around.
+    pub(crate) fn cache_dir(&self) -> Utf8PathBuf {
+        self.project_root().join(&self.cache_dir)
+    }
+
     /// Get a copy of all the paths that are inputs of the build.
     ///
     /// Used by hinoki-dev-server to classify changes within the project root.
@@ -59,6 +120,7 @@ impl Config {
         Inputs {
             project_root: self.project_root().to_owned(),
             config_file: self.path.file_name().expect("config file must have a name").to_owned(),
+            include_files: self.include_files.clone(),
             content_dir: self.content_dir.clone(),
             asset_dir: self.asset_dir.clone(),
             template_dir: self.template_dir.clone(),
@@ -70,6 +132,15 @@ impl Config {
         self.output_dir = value;
     }
 
+    /// Overrides where the incremental build cache's manifest is persisted.
+    ///
+    /// Used by the dev server and the integration tests, which both build
+    /// into a throwaway output directory and shouldn't leave a `.hinoki-cache`
+    /// behind in the project root.
+    pub fn set_cache_dir(&mut self, value: Utf8PathBuf) {
+        self.cache_dir = value;
+    }
+
     /// Get the "project root", that is the parent directory of the config file.
     ///
     /// Content, asset and output directory paths from the config are treated
@@ -100,6 +171,10 @@ fn default_output_dir() -> Utf8PathBuf {
     "build".into()
 }
 
+fn default_cache_dir() -> Utf8PathBuf {
+    ".hinoki-cache".into()
+}
+
 #[derive(Clone)]
 pub struct ContentFileSettings {
     values: Vec<ContentFileConfig>,
@@ -149,6 +224,50 @@ impl<'de> Deserialize<'de> for ContentFileSettings {
     }
 }
 
+#[derive(Clone)]
+pub struct AssetFileSettings {
+    values: Vec<AssetFileConfig>,
+    globset: GlobSet,
+}
+
+impl AssetFileSettings {
+    pub(crate) fn from_map(
+        map: IndexMap<String, AssetFileConfig>,
+    ) -> Result<Self, globset::Error> {
+        let mut builder = GlobSetBuilder::new();
+        for path_glob in map.keys() {
+            builder.add(Glob::new(path_glob)?);
+        }
+        let globset = builder.build()?;
+        let values = map.into_values().collect();
+        Ok(Self { values, globset })
+    }
+
+    pub(crate) fn for_path(
+        &self,
+        path: &Utf8Path,
+    ) -> impl DoubleEndedIterator<Item = &AssetFileConfig> {
+        self.globset.matches(path).into_iter().map(|idx| &self.values[idx])
+    }
+}
+
+impl Default for AssetFileSettings {
+    fn default() -> Self {
+        // Fingerprinting is opt-in, so there's nothing to configure by default.
+        Self::from_map(IndexMap::new()).unwrap()
+    }
+}
+
+impl<'de> Deserialize<'de> for AssetFileSettings {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let map: IndexMap<String, AssetFileConfig> = IndexMap::deserialize(deserializer)?;
+        Self::from_map(map).map_err(de::Error::custom)
+    }
+}
+
 /// Inputs to a hinoki project.
 pub struct Inputs {
     /// The "project root", i.e. the parent directory of the config file.
@@ -157,6 +276,10 @@ pub struct Inputs {
     /// The name of the config file.
     pub config_file: String,
 
+    /// Canonicalized paths of every file included (transitively) via the
+    /// config's `include` key.
+    pub include_files: Vec<Utf8PathBuf>,
+
     /// The content directory, relative to the project root.
     pub content_dir: Utf8PathBuf,
 
@@ -169,3 +292,291 @@ pub struct Inputs {
     /// The sublime syntax directory, relative to the project root.
     pub sublime_dir: Utf8PathBuf,
 }
+
+/// Reads `path` as TOML and recursively merges in any files named by its
+/// `include` key, returning the merged table and the canonicalized paths of
+/// every file that was read (`path` itself first, then each included file in
+/// the order it was first encountered).
+///
+/// Include paths are resolved relative to the directory of the file that
+/// names them. Entries in `include` are merged first-to-last (a later
+/// include overrides an earlier one), and the including file's own keys
+/// override everything it includes. Include cycles are reported as errors.
+pub(crate) fn read_merged_config_table(
+    path: &Utf8Path,
+    config_str: &str,
+) -> anyhow::Result<(toml::Table, Vec<Utf8PathBuf>)> {
+    let table: toml::Table =
+        toml::from_str(config_str).with_context(|| format!("Failed to parse `{path}`"))?;
+
+    let mut include_files = Vec::new();
+    let mut stack = Vec::new();
+    let table = merge_includes(path, table, &mut stack, &mut include_files)?;
+    Ok((table, include_files))
+}
+
+fn merge_includes(
+    path: &Utf8Path,
+    mut table: toml::Table,
+    stack: &mut Vec<Utf8PathBuf>,
+    include_files: &mut Vec<Utf8PathBuf>,
+) -> anyhow::Result<toml::Table> {
+    let abs_path =
+        path.canonicalize_utf8().with_context(|| format!("Failed to resolve `{path}`"))?;
+    if stack.contains(&abs_path) {
+        let cycle = stack.iter().map(Utf8PathBuf::as_str).collect::<Vec<_>>().join("` -> `");
+        anyhow::bail!("include cycle detected: `{cycle}` -> `{abs_path}`");
+    }
+    include_files.push(abs_path.clone());
+
+    let Some(include) = table.remove("include") else {
+        return Ok(table);
+    };
+    let include_paths = Vec::<Utf8PathBuf>::deserialize(include)
+        .with_context(|| format!("`include` in `{path}` must be an array of paths"))?;
+
+    let dir = path.parent().expect("config path must have a parent");
+    stack.push(abs_path);
+
+    let mut merged = toml::Table::new();
+    for include_path in include_paths {
+        let include_path = dir.join(include_path);
+        let include_str = fs::read_to_string(&include_path)
+            .with_context(|| format!("Failed to open `{include_path}`"))?;
+        let include_table: toml::Table = toml::from_str(&include_str)
+            .with_context(|| format!("Failed to parse `{include_path}`"))?;
+        let include_table = merge_includes(&include_path, include_table, stack, include_files)
+            .with_context(|| format!("including `{include_path}` from `{path}`"))?;
+        merge_toml_table(&mut merged, include_table);
+    }
+
+    stack.pop();
+    merge_toml_table(&mut merged, table);
+    Ok(merged)
+}
+
+/// Merges `overrides` into `base`, recursing into nested tables so that e.g.
+/// `[extra]` keys from an include survive unless the including file sets the
+/// same key. Non-table values in `overrides` simply replace the value in
+/// `base`.
+fn merge_toml_table(base: &mut toml::Table, overrides: toml::Table) {
+    for (key, value) in overrides {
+        match base.entry(key) {
+            TomlMapEntry::Occupied(mut entry) => {
+                let both_tables =
+                    matches!((entry.get(), &value), (toml::Value::Table(_), toml::Value::Table(_)));
+                if both_tables {
+                    let (toml::Value::Table(target), toml::Value::Table(source)) =
+                        (entry.get_mut(), value)
+                    else {
+                        unreachable!()
+                    };
+                    merge_toml_table(target, source);
+                } else {
+                    entry.insert(value);
+                }
+            }
+            TomlMapEntry::Vacant(entry) => {
+                entry.insert(value);
+            }
+        }
+    }
+}
+
+/// Configuration for the CommonMark renderer.
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct MarkdownConfig {
+    /// Enable smart punctuation (curly quotes, en/em dashes, ellipses).
+    pub smart_punctuation: bool,
+
+    /// Enable GitHub-flavored tables.
+    pub tables: bool,
+
+    /// Enable footnotes.
+    pub footnotes: bool,
+
+    /// Enable strikethrough (`~~text~~`).
+    pub strikethrough: bool,
+
+    /// Enable task lists (`- [ ]` / `- [x]`).
+    pub task_lists: bool,
+
+    /// Enable heading attributes (`# Heading {#custom-id}`).
+    pub heading_attributes: bool,
+
+    /// Open links to external sites in a new tab (`target="_blank"`).
+    pub external_links_target_blank: bool,
+
+    /// Add `rel="nofollow"` to links to external sites.
+    pub external_links_no_follow: bool,
+
+    /// Add `rel="noreferrer"` to links to external sites.
+    pub external_links_no_referrer: bool,
+}
+
+impl Default for MarkdownConfig {
+    fn default() -> Self {
+        Self {
+            smart_punctuation: false,
+            tables: false,
+            // Preserve the hardcoded behavior from before this config section existed.
+            footnotes: true,
+            strikethrough: false,
+            task_lists: false,
+            heading_attributes: false,
+            external_links_target_blank: false,
+            external_links_no_follow: false,
+            external_links_no_referrer: false,
+        }
+    }
+}
+
+impl MarkdownConfig {
+    pub(crate) fn pulldown_options(&self) -> pulldown_cmark::Options {
+        let mut options = pulldown_cmark::Options::ENABLE_FOOTNOTES;
+        options.set(pulldown_cmark::Options::ENABLE_SMART_PUNCTUATION, self.smart_punctuation);
+        options.set(pulldown_cmark::Options::ENABLE_TABLES, self.tables);
+        options.set(pulldown_cmark::Options::ENABLE_FOOTNOTES, self.footnotes);
+        options.set(pulldown_cmark::Options::ENABLE_STRIKETHROUGH, self.strikethrough);
+        options.set(pulldown_cmark::Options::ENABLE_TASKLISTS, self.task_lists);
+        options.set(pulldown_cmark::Options::ENABLE_HEADING_ATTRIBUTES, self.heading_attributes);
+        options
+    }
+
+    pub(crate) fn rewrites_external_links(&self) -> bool {
+        self.external_links_target_blank
+            || self.external_links_no_follow
+            || self.external_links_no_referrer
+    }
+}
+
+/// Configuration for multilingual content.
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct LanguagesConfig {
+    /// The site's primary language.
+    ///
+    /// Pages in this language keep their current un-prefixed output path
+    /// (e.g. `/about/`), for backward compatibility with single-language
+    /// sites. Pages in any other language get their output path prefixed
+    /// with the language code (e.g. `/fr/about/`).
+    pub default: String,
+
+    /// Additional language codes the site is translated into, e.g. `["fr",
+    /// "de"]`.
+    pub others: Vec<String>,
+}
+
+impl Default for LanguagesConfig {
+    fn default() -> Self {
+        Self { default: "en".to_owned(), others: Vec::new() }
+    }
+}
+
+/// Configuration for syntax-highlighted code blocks.
+#[derive(Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct SyntaxHighlightConfig {
+    /// How syntax-highlighted code blocks should be rendered.
+    ///
+    /// Pair [`SyntaxHighlightStyle::Classed`] with the `syntect-to-css` CLI
+    /// subcommand to export the matching stylesheet for a theme once,
+    /// instead of repeating its colors inline on every page.
+    pub style: SyntaxHighlightStyle,
+}
+
+/// Output style for syntax-highlighted code blocks.
+#[derive(Clone, Copy, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SyntaxHighlightStyle {
+    /// Inline per-token `style="..."` attributes. Simple, but bloats output
+    /// and bakes in a single theme.
+    #[default]
+    Inline,
+    /// `<span class="...">` tokens referencing scope classes from a
+    /// generated stylesheet (see [`Build`][crate::build::Build]), so themes
+    /// can be switched or combined without re-rendering content.
+    Classed,
+}
+
+/// Configuration for ordering a directory's files as seen by templates
+/// (`dir.files`, `get_files`) and in `prev`/`next` pagination links.
+#[derive(Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct SortConfig {
+    /// Primary key to sort a directory's files by.
+    pub by: SortKey,
+
+    /// Reverse the order produced by `by`.
+    pub reverse: bool,
+}
+
+/// Configuration for build-time gzip/Brotli precompression of output files.
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct PrecompressionConfig {
+    /// Output file extensions worth compressing. Defaults to textual
+    /// formats that compress well; already-compressed formats (images,
+    /// fonts, etc.) should be left out.
+    pub extensions: Vec<String>,
+
+    /// Files smaller than this many bytes aren't compressed: the `.gz`/`.br`
+    /// files plus the `Content-Encoding` negotiation overhead would outweigh
+    /// the savings.
+    pub min_size: u64,
+}
+
+impl Default for PrecompressionConfig {
+    fn default() -> Self {
+        Self {
+            extensions: ["html", "css", "js", "svg", "json", "xml"].into_iter().map(String::from).collect(),
+            min_size: 1024,
+        }
+    }
+}
+
+/// Configuration for the post-build internal link checker.
+#[derive(Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct LinkCheckConfig {
+    /// Scan every emitted HTML file for broken internal `href`/`src`
+    /// targets (including dangling `#id` anchors) once the build finishes,
+    /// failing the build if any are found.
+    ///
+    /// Off by default, since it adds a full pass over the output directory;
+    /// turn it on once link/slug churn has settled, e.g. in CI.
+    pub enabled: bool,
+
+    /// Also validate external `http(s)` links over the network. Requires
+    /// the `link-checking` feature. A broken external link only ever
+    /// produces a warning, never fails the build: external sites are
+    /// outside hinoki's control and can be down or rate-limiting
+    /// temporarily.
+    pub external: bool,
+
+    /// Domains to skip when checking external links, e.g. sites known to
+    /// block automated requests.
+    pub skip_domains: Vec<String>,
+}
+
+/// Key used to order a directory's files, mirroring Zola's `sort_by`.
+///
+/// Whichever key is chosen, files that tie on it (including every file, for
+/// [`SortKey::None`]) fall back to ascending `slug` order, so the result is
+/// reproducible across machines regardless of filesystem read order.
+#[derive(Clone, Copy, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SortKey {
+    /// No explicit ordering; files are ordered by `slug` alone. The default.
+    #[default]
+    None,
+    /// Frontmatter `date`, oldest first. Files without a `date` sort last.
+    Date,
+    /// Frontmatter `title`, alphabetically. Files without a `title` sort last.
+    Title,
+    /// The page's (possibly overridden) `slug`.
+    Slug,
+    /// Frontmatter `weight`, ascending. Files without a `weight` sort last.
+    Weight,
+}
@@ -1,5 +1,6 @@
 use std::{
-    collections::BTreeMap,
+    cmp::Ordering as CmpOrdering,
+    collections::{BTreeMap, HashSet},
     io::{self, BufReader, BufWriter, Read, Seek, Write},
     sync::{
         Arc, OnceLock,
@@ -15,33 +16,49 @@ use indexmap::IndexMap;
 use itertools::Itertools as _;
 use minijinja::{context, value::Object};
 use rayon::iter::{IntoParallelRefIterator as _, ParallelIterator as _};
-use serde::{Serialize, Serializer};
+use serde::{Serialize, Serializer, ser::SerializeStruct as _};
 use smallvec::SmallVec;
 use tracing::{error, instrument, warn};
 
 use crate::{
-    build::OutputDirManager,
-    config::Config,
+    build::{
+        OutputDirManager,
+        cache::{CacheEntry, IncrementalCache},
+    },
+    config::{Config, LanguagesConfig, LinkCheckConfig, SortConfig, SortKey},
     frontmatter::parse_frontmatter,
     metadata::metadata_env,
     template::context::{
-        DirectoryContext, GlobalContext, HinokiContext, RenderContext, TemplateContext,
-        serialize_hinoki_cx,
+        DirectoryContext, GlobalContext, HinokiContext, PageContext, RenderContext,
+        TemplateContext, serialize_hinoki_cx,
     },
-    util::HinokiDatetime,
+    util::{HinokiDatetime, content_hash},
 };
 
 mod file_config;
+mod link_check;
 #[cfg(feature = "markdown")]
 mod markdown;
+#[cfg(feature = "minify-html")]
+mod minify;
+#[cfg(feature = "markdown")]
+mod processors;
+#[cfg(feature = "markdown")]
+mod shortcodes;
 #[cfg(feature = "syntax-highlighting")]
 mod syntax_highlighting;
+mod taxonomies;
+#[cfg(feature = "markdown")]
+mod toc;
 
 pub(crate) use self::file_config::{ContentFileConfig, ProcessContent};
 #[cfg(feature = "markdown")]
 pub(crate) use self::markdown::markdown_to_html;
 #[cfg(feature = "syntax-highlighting")]
 pub(crate) use self::syntax_highlighting::{LazySyntaxHighlighter, SyntaxHighlighter};
+use self::taxonomies::TermListEntry;
+#[cfg(feature = "markdown")]
+pub(crate) use self::toc::TocEntry;
 
 pub(crate) struct ContentProcessor<'c, 's, 'sc> {
     // FIXME: args, template_env, syntax_highlighter (in cx) plus render_scope
@@ -62,16 +79,75 @@ impl<'c: 'sc, 's, 'sc> ContentProcessor<'c, 's, 'sc> {
         Self { metadata_env, render_scope, cx }
     }
 
-    pub(crate) fn run(&self) -> anyhow::Result<()> {
-        self.process_content_dir(&self.cx.content_dir, WriteOutput::Yes)?;
-        Ok(())
+    /// Walks the whole content directory, rendering every file and
+    /// returning its metadata tree for [`Build`][crate::build::Build] to
+    /// keep around for [`Self::rebuild_subtree`] to splice into later.
+    pub(crate) fn run(&self) -> anyhow::Result<DirectoryMetadata> {
+        let root = self.process_content_dir(&self.cx.content_dir, WriteOutput::Yes)?;
+        self.render_taxonomies(&root)?;
+
+        if self.cx.config.link_check.enabled {
+            match link_check::check_links(self.cx.output_dir_mgr, &self.cx.config.link_check) {
+                Ok(has_broken_links) => {
+                    if has_broken_links {
+                        self.cx.did_error.store(true, Ordering::Relaxed);
+                    }
+                }
+                Err(e) => {
+                    error!("link check failed: {e:#}");
+                    self.cx.did_error.store(true, Ordering::Relaxed);
+                }
+            }
+        }
+
+        Ok(root)
     }
 
-    pub(crate) fn dump(&self) -> anyhow::Result<()> {
-        let metadata = self.process_content_dir(&self.cx.content_dir, WriteOutput::No)?;
-        println!("{metadata:#?}");
+    pub(crate) fn dump(&self) -> anyhow::Result<DirectoryMetadata> {
+        self.process_content_dir(&self.cx.content_dir, WriteOutput::No)
+    }
 
-        Ok(())
+    /// Recomputes `dir`'s metadata (re-rendering its own files and
+    /// recursing into any subdirectories beneath it as usual) and splices
+    /// the result into `root` at `path_components` (directory names
+    /// relative to the content dir, outermost first), then regenerates
+    /// taxonomy pages and runs the link checker (if enabled) against the
+    /// spliced tree — both aggregate across the whole site and can't be
+    /// limited to just the changed subtree. Sibling directories elsewhere
+    /// in `root` are left untouched and not reprocessed, which is what
+    /// makes [`crate::build::Build::rebuild_subpath`] cheap.
+    ///
+    /// External link checking never runs here even if configured on: this
+    /// path is the dev server's fast incremental rebuild, and re-hitting
+    /// every external URL on the whole site's network over again on every
+    /// save would defeat the point.
+    pub(crate) fn rebuild_subtree(
+        &self,
+        root: &DirectoryMetadata,
+        path_components: &[String],
+        dir: &Utf8Path,
+    ) -> anyhow::Result<DirectoryMetadata> {
+        let new_subtree = self.process_content_dir(dir, WriteOutput::Yes)?;
+        let spliced = splice_subtree(root, path_components, new_subtree);
+        self.render_taxonomies(&spliced)?;
+
+        if self.cx.config.link_check.enabled {
+            let link_check_config =
+                LinkCheckConfig { external: false, ..self.cx.config.link_check.clone() };
+            match link_check::check_links(self.cx.output_dir_mgr, &link_check_config) {
+                Ok(has_broken_links) => {
+                    if has_broken_links {
+                        self.cx.did_error.store(true, Ordering::Relaxed);
+                    }
+                }
+                Err(e) => {
+                    error!("link check failed: {e:#}");
+                    self.cx.did_error.store(true, Ordering::Relaxed);
+                }
+            }
+        }
+
+        Ok(spliced)
     }
 
     fn process_content_dir(
@@ -119,51 +195,205 @@ impl<'c: 'sc, 's, 'sc> ContentProcessor<'c, 's, 'sc> {
         // FIXME: Is it possible to make some sort of Flatten FromIterator
         // adapter that combines with the Result FromIterator impl such that
         // this doesn't need to be an explicit fold?
-        let files = files.iter().try_fold(Vec::new(), |mut v, path| {
-            v.extend(
-                self.process_content_file(path, &mut output_file_idx, dir_cx.clone(), write_output)
+        //
+        // Metadata for every file in the directory is computed up front (in
+        // file order, so `dir_output_file_idx` stays deterministic) before
+        // any rendering happens, so that `attach_translations` can link
+        // pages sharing a language-stripped stem before the language
+        // switcher data they need is handed off to `render_file`.
+        let mut pending = files.iter().try_fold(Vec::new(), |mut v, path| {
+            v.push(
+                self.content_file_metadata(path, &mut output_file_idx, dir_cx.clone())
                     .with_context(|| format!("processing `{path}`"))?,
             );
 
             anyhow::Ok(v)
         })?;
 
+        attach_translations(&mut pending);
+        self.expand_pagination(&mut pending, &mut output_file_idx)?;
+
+        let mut files = pending.into_iter().try_fold(Vec::new(), |mut v, pending_file| {
+            if let WriteOutput::Yes = write_output {
+                self.dispatch_render(&pending_file)
+                    .with_context(|| format!("processing `{}`", pending_file.content_path))?;
+            }
+
+            v.extend(pending_file.meta);
+            anyhow::Ok(v)
+        })?;
+
+        sort_files(&mut files, &self.cx.config.sort);
         dir_cx.set_files(files);
         Ok(dir_cx.into_metadata())
     }
 
     #[instrument(skip_all, fields(?content_path))]
-    fn process_content_file(
+    fn content_file_metadata(
         &self,
         content_path: &Utf8Path,
         dir_output_file_idx: &mut usize,
         dir_cx: DirectoryContext,
-        write_output: WriteOutput,
-    ) -> anyhow::Result<SmallVec<[FileMetadata; 1]>> {
-        let source_path: Arc<Utf8Path> =
-            content_path.strip_prefix(&self.cx.content_dir).context("invalid content_path")?.into();
+    ) -> anyhow::Result<PendingFile> {
+        let raw_source_path =
+            content_path.strip_prefix(&self.cx.content_dir).context("invalid content_path")?;
+        let (source_path, filename_lang) =
+            split_language_suffix(raw_source_path, &self.cx.config.languages);
 
         let mut input_file = BufReader::new(File::open(content_path)?);
 
         let frontmatter = parse_frontmatter(&mut input_file)?;
-        let all_file_meta =
-            self.all_file_metadata(source_path.clone(), dir_output_file_idx, dir_cx, frontmatter)?;
+        let frontmatter_end = input_file
+            .stream_position()
+            .context("failed to get end of frontmatter file position")?;
+
+        let (meta, pagination) = self.all_file_metadata(
+            source_path,
+            filename_lang,
+            dir_output_file_idx,
+            dir_cx,
+            frontmatter,
+        )?;
+
+        Ok(PendingFile {
+            content_path: content_path.to_owned(),
+            frontmatter_end,
+            cache_key: raw_source_path.to_owned(),
+            translation_group_key: source_path.clone(),
+            meta,
+            pagination,
+        })
+    }
 
-        if let WriteOutput::No = write_output {
-            return Ok(all_file_meta);
+    /// Expands every [`PendingFile`] with a `paginate_by` frontmatter key
+    /// into its pages, now that the rest of the directory's files have their
+    /// metadata computed. Siblings are sorted per the configured `[sort]`
+    /// (the same order `dir.files` and `get_files` use) before being split
+    /// into `per_page`-sized chunks, so pagination is deterministic rather
+    /// than depending on `fs::read_dir` order.
+    fn expand_pagination(
+        &self,
+        pending: &mut [PendingFile],
+        dir_output_file_idx: &mut usize,
+    ) -> anyhow::Result<()> {
+        let mut siblings: Vec<FileMetadata> =
+            pending.iter().flat_map(|pending_file| pending_file.meta.iter().cloned()).collect();
+        sort_files(&mut siblings, &self.cx.config.sort);
+
+        for pending_file in pending.iter_mut() {
+            let Some(pagination) = pending_file.pagination.take() else { continue };
+            pending_file.meta = self
+                .paginate_file(pagination, dir_output_file_idx, &siblings)
+                .with_context(|| format!("processing `{}`", pending_file.content_path))?;
         }
 
-        match all_file_meta.clone().into_inner() {
+        Ok(())
+    }
+
+    fn paginate_file(
+        &self,
+        pagination: PendingPagination,
+        dir_output_file_idx: &mut usize,
+        siblings: &[FileMetadata],
+    ) -> anyhow::Result<SmallVec<[FileMetadata; 1]>> {
+        let PendingPagination { source_path, lang, dir_cx, frontmatter, per_page } = pagination;
+
+        let chunks: Vec<&[FileMetadata]> =
+            if siblings.is_empty() { vec![&[]] } else { siblings.chunks(per_page).collect() };
+        let total_pages = chunks.len();
+
+        let hinoki_cx_source_path = source_path.clone();
+        let make_hinoki_cx = |dir_output_file_idx| {
+            HinokiContext::new(
+                self.cx.template_global_cx.clone(),
+                dir_cx.to_owned(),
+                RenderContext::new(
+                    dir_output_file_idx,
+                    hinoki_cx_source_path.clone(),
+                    #[cfg(feature = "syntax-highlighting")]
+                    frontmatter.syntax_highlight_theme.clone(),
+                ),
+            )
+        };
+
+        let mut file_metas: SmallVec<[FileMetadata; 1]> = chunks
+            .iter()
+            .enumerate()
+            .map(|(page_idx, files)| {
+                let paginator = Some(Paginator {
+                    number: page_idx + 1,
+                    current_index: page_idx,
+                    total_pages,
+                    files: files.to_vec(),
+                    // Filled in by the pass below, once every page's path is known.
+                    prev_page_path: None,
+                    next_page_path: None,
+                });
+                self.file_metadata(
+                    source_path.clone(),
+                    lang.clone(),
+                    dir_output_file_idx,
+                    &frontmatter,
+                    make_hinoki_cx,
+                    None,
+                    paginator,
+                )
+            })
+            .collect::<anyhow::Result<_>>()?;
+
+        for page_idx in 0..file_metas.len() {
+            let prev_page_path = page_idx.checked_sub(1).map(|i| file_metas[i].path.clone());
+            let next_page_path = file_metas.get(page_idx + 1).map(|m| m.path.clone());
+
+            file_metas[page_idx].paginator = Some(minijinja::Value::from_serialize(Paginator {
+                number: page_idx + 1,
+                current_index: page_idx,
+                total_pages,
+                files: chunks[page_idx].to_vec(),
+                prev_page_path,
+                next_page_path,
+            }));
+        }
+
+        Ok(file_metas)
+    }
+
+    /// Renders the output file(s) described by `pending_file`'s metadata,
+    /// unless it's been excluded from an incremental rebuild.
+    fn dispatch_render(&self, pending_file: &PendingFile) -> anyhow::Result<()> {
+        // In an incremental rebuild, metadata is still computed for every
+        // file (other pages may depend on it via `get_file`/`get_files`), but
+        // only files in the allow-list are actually re-rendered.
+        if let Some(render_only) = &self.cx.render_only
+            && !render_only.contains(&pending_file.content_path)
+        {
+            return Ok(());
+        }
+
+        if let Some(incremental) = &self.cx.incremental
+            && self.record_and_check_up_to_date(incremental, pending_file)?
+        {
+            return Ok(());
+        }
+
+        match pending_file.meta.clone().into_inner() {
             // We want to produce exactly one output file.
-            //
-            // Reuse the already-opened input file.
             Ok([file_meta]) => {
-                self.render_file(file_meta, input_file, content_path.to_owned())?;
+                let mut input_file = BufReader::new(File::open(&pending_file.content_path)?);
+                input_file
+                    .seek_relative(pending_file.frontmatter_end as _)
+                    .context("failed to seek over frontmatter")?;
+                self.render_file(
+                    file_meta,
+                    input_file,
+                    pending_file.content_path.clone(),
+                    pending_file.cache_key.clone(),
+                )?;
             }
             // We want to produce zero or multiple output files.
             //
-            // Get the input file position and reopen the file at that position
-            // for every render_file call.
+            // Reopen the file and seek past the frontmatter for every
+            // render_file call.
             //
             // FIXME: This opens the file one more time than necessary, what's
             // a convenient way around that?
@@ -173,31 +403,70 @@ impl<'c: 'sc, 's, 'sc> ContentProcessor<'c, 's, 'sc> {
             // likely marginally better for perf. See this article:
             // https://blog.gnoack.org/post/proc-fd-is-not-dup/
             Err(all_file_meta) => {
-                let pos = input_file
-                    .stream_position()
-                    .context("failed to get end of frontmatter file position")?;
-                drop(input_file);
-
                 for file_meta in all_file_meta {
-                    let mut input_file = BufReader::new(File::open(content_path)?);
+                    let mut input_file = BufReader::new(File::open(&pending_file.content_path)?);
                     input_file
-                        .seek_relative(pos as _)
+                        .seek_relative(pending_file.frontmatter_end as _)
                         .context("failed to seek over frontmatter")?;
-                    self.render_file(file_meta, input_file, content_path.to_owned())?;
+                    self.render_file(
+                        file_meta,
+                        input_file,
+                        pending_file.content_path.clone(),
+                        pending_file.cache_key.clone(),
+                    )?;
                 }
             }
         }
 
-        Ok(all_file_meta)
+        Ok(())
+    }
+
+    /// Records `pending_file`'s current cache entry in the manifest for the
+    /// build in progress, and reports whether it's identical to the entry
+    /// the previous build recorded (and that entry wasn't flagged
+    /// aggregate), meaning `dispatch_render` can skip re-rendering it. An
+    /// up-to-date page's previous output paths are re-registered with
+    /// `OutputDirManager`, since skipping rendering also skips the
+    /// registration `OutputDirManager::output_path` would otherwise have
+    /// done.
+    ///
+    /// The entry recorded here always starts out not flagged aggregate;
+    /// `render_file` upgrades it afterwards via
+    /// `NewManifest::mark_aggregate` once it's known whether the page's
+    /// render actually read data beyond its own content file, which can't be
+    /// determined before rendering.
+    fn record_and_check_up_to_date(
+        &self,
+        incremental: &IncrementalCache<'_>,
+        pending_file: &PendingFile,
+    ) -> anyhow::Result<bool> {
+        let content_hash = content_hash(fs::read(&pending_file.content_path)?);
+        let output_paths = pending_file
+            .meta
+            .iter()
+            .map(|meta| self.cx.output_dir_mgr.resolve_output_path(&meta.path))
+            .collect();
+
+        let entry = CacheEntry::new(content_hash, incremental.config_hash.clone(), output_paths);
+        let up_to_date = incremental.previous.is_up_to_date(&pending_file.cache_key, &entry);
+        if up_to_date {
+            for output_path in entry.output_paths() {
+                self.cx.output_dir_mgr.register_output(output_path.clone());
+            }
+        }
+        incremental.new_manifest.record(&pending_file.cache_key, entry);
+
+        Ok(up_to_date)
     }
 
     fn all_file_metadata(
         &self,
         source_path: Arc<Utf8Path>,
+        filename_lang: Option<String>,
         dir_output_file_idx: &mut usize,
         dir_cx: DirectoryContext,
         mut frontmatter: ContentFileConfig,
-    ) -> anyhow::Result<SmallVec<[FileMetadata; 1]>> {
+    ) -> anyhow::Result<(SmallVec<[FileMetadata; 1]>, Option<PendingPagination>)> {
         #[derive(Serialize)]
         pub(crate) struct RepeatContext {
             #[serde(rename = "$hinoki_cx", serialize_with = "serialize_hinoki_cx")]
@@ -209,7 +478,7 @@ impl<'c: 'sc, 's, 'sc> ContentProcessor<'c, 's, 'sc> {
         }
 
         if !self.cx.include_drafts && frontmatter.draft.unwrap_or(false) {
-            return Ok(SmallVec::new());
+            return Ok((SmallVec::new(), None));
         }
 
         #[cfg(not(feature = "syntax-highlighting"))]
@@ -220,12 +489,34 @@ impl<'c: 'sc, 's, 'sc> ContentProcessor<'c, 's, 'sc> {
             );
         }
 
+        let lang: Arc<str> = frontmatter
+            .lang
+            .clone()
+            .or(filename_lang)
+            .unwrap_or_else(|| self.cx.config.languages.default.clone())
+            .into();
+
+        if let Some(per_page) = frontmatter.paginate_by {
+            anyhow::ensure!(
+                frontmatter.repeat.is_none(),
+                "`paginate_by` cannot be combined with `repeat`"
+            );
+            anyhow::ensure!(per_page > 0, "`paginate_by` must be greater than zero");
+
+            return Ok((
+                SmallVec::new(),
+                Some(PendingPagination { source_path, lang, dir_cx, frontmatter, per_page }),
+            ));
+        }
+
+        let hinoki_cx_source_path = source_path.clone();
         let make_hinoki_cx = |dir_output_file_idx| {
             HinokiContext::new(
                 self.cx.template_global_cx.clone(),
                 dir_cx.to_owned(),
                 RenderContext::new(
                     dir_output_file_idx,
+                    hinoki_cx_source_path.clone(),
                     #[cfg(feature = "syntax-highlighting")]
                     frontmatter.syntax_highlight_theme.clone(),
                 ),
@@ -245,13 +536,14 @@ impl<'c: 'sc, 's, 'sc> ContentProcessor<'c, 's, 'sc> {
                 repeat_val.try_iter().context("repeat value is not iterable")?.collect();
             let total_pages = repeat_items.len();
 
-            repeat_items
-                .into_iter()
+            let mut file_metas: SmallVec<[FileMetadata; 1]> = repeat_items
+                .iter()
                 .enumerate()
                 .map(|(repeat_idx, item)| {
                     let repeat = Some(Repeat {
-                        item,
-                        // FIXME: Do another pass to propagate these
+                        item: item.clone(),
+                        // Filled in by the pass below, once every repeat
+                        // instance's metadata is known.
                         prev_page: None,
                         next_page: None,
                         current_index: repeat_idx,
@@ -259,41 +551,70 @@ impl<'c: 'sc, 's, 'sc> ContentProcessor<'c, 's, 'sc> {
                     });
                     self.file_metadata(
                         source_path.clone(),
+                        lang.clone(),
                         dir_output_file_idx,
                         &frontmatter,
                         make_hinoki_cx,
                         repeat,
+                        None,
                     )
                 })
-                .collect()
+                .collect::<anyhow::Result<_>>()?;
+
+            // Second pass (like Zola's paginator): now that every repeat
+            // instance's metadata has been computed, link each one to its
+            // neighbors so templates can render prev/next pagination links.
+            for repeat_idx in 0..file_metas.len() {
+                let prev_page =
+                    repeat_idx.checked_sub(1).map(|i| RepeatFileMetadata::from(&file_metas[i]));
+                let next_page = file_metas.get(repeat_idx + 1).map(RepeatFileMetadata::from);
+
+                file_metas[repeat_idx].repeat = Some(minijinja::Value::from_serialize(Repeat {
+                    item: repeat_items[repeat_idx].clone(),
+                    prev_page,
+                    next_page,
+                    current_index: repeat_idx,
+                    total_pages,
+                }));
+            }
+
+            Ok((file_metas, None))
         } else {
             let meta = self.file_metadata(
                 source_path,
+                lang,
                 dir_output_file_idx,
                 &frontmatter,
                 make_hinoki_cx,
                 None,
+                None,
             )?;
-            Ok(SmallVec::from_elem(meta, 1))
+            Ok((SmallVec::from_elem(meta, 1), None))
         }
     }
 
     fn file_metadata(
         &self,
         source_path: Arc<Utf8Path>,
+        lang: Arc<str>,
         dir_output_file_idx: &mut usize,
         frontmatter: &ContentFileConfig,
         make_hinoki_cx: impl Fn(Option<usize>) -> Arc<HinokiContext>,
         repeat: Option<Repeat>,
+        paginator: Option<Paginator>,
     ) -> anyhow::Result<FileMetadata> {
         let repeat = repeat.map(minijinja::Value::from_serialize);
+        let paginator = paginator.map(minijinja::Value::from_serialize);
 
         let mut metadata_cx = Arc::new(MetadataContext {
             source_path: source_path.clone(),
+            lang: lang.clone(),
             slug: None,
             title: None,
             date: None,
+            weight: None,
             repeat: repeat.clone(),
+            paginator: paginator.clone(),
         });
 
         let slug = self
@@ -314,28 +635,39 @@ impl<'c: 'sc, 's, 'sc> ContentProcessor<'c, 's, 'sc> {
                 .context("parsing date field")?,
             None => None,
         };
+        let weight = frontmatter.weight;
 
-        // Make slug, title and date available for path templates
+        // Make slug, title, date and weight available for path templates
         {
             let metadata_cx = Arc::make_mut(&mut metadata_cx);
             metadata_cx.slug = Some(slug.clone());
             metadata_cx.title = title.clone();
             metadata_cx.date = date;
+            metadata_cx.weight = weight;
         }
 
-        let path = match self.expand_metadata_tpl(frontmatter.path.as_deref(), &metadata_cx)? {
-            Some(path) => Utf8Path::new(
-                path.strip_prefix('/')
-                    .context("paths in frontmatter and config.content must begin with '/'")?,
-            )
-            .into(),
-            None => source_path,
+        let path: Arc<Utf8Path> =
+            match self.expand_metadata_tpl(frontmatter.path.as_deref(), &metadata_cx)? {
+                Some(path) => Utf8Path::new(
+                    path.strip_prefix('/')
+                        .context("paths in frontmatter and config.content must begin with '/'")?,
+                )
+                .into(),
+                None => source_path,
+            };
+        // Pages in the default language keep their un-prefixed path, for
+        // backward compatibility with single-language sites.
+        let path: Arc<Utf8Path> = if *lang == *self.cx.config.languages.default {
+            path
+        } else {
+            Utf8Path::new(&*lang).join(&*path).into()
         };
 
         let draft = frontmatter.draft.unwrap_or(false);
         let extra = frontmatter.extra.clone();
+        let taxonomies = frontmatter.taxonomies.clone();
         let template = frontmatter.template.clone();
-        let process = frontmatter.process;
+        let process = frontmatter.process.clone();
 
         let hinoki_cx = make_hinoki_cx(Some(*dir_output_file_idx));
         *dir_output_file_idx += 1;
@@ -346,8 +678,15 @@ impl<'c: 'sc, 's, 'sc> ContentProcessor<'c, 's, 'sc> {
             path,
             title,
             date,
+            lang,
+            // Filled in by `attach_translations` once every page in the
+            // directory has its final `path` computed.
+            translations: IndexMap::new(),
             extra,
+            taxonomies,
+            weight,
             repeat,
+            paginator,
             template,
             process,
             hinoki_cx,
@@ -376,29 +715,110 @@ impl<'c: 'sc, 's, 'sc> ContentProcessor<'c, 's, 'sc> {
         file_meta: FileMetadata,
         input_file: BufReader<File>,
         content_path: Utf8PathBuf,
+        cache_key: Utf8PathBuf,
     ) -> anyhow::Result<()> {
         #[cfg(not(feature = "markdown"))]
-        if let Some(ProcessContent::MarkdownToHtml) = file_meta.process {
+        if let Some(process) = &file_meta.process {
             anyhow::bail!(
-                "hinoki was compiled without support for markdown.\
+                "hinoki was compiled without support for content processors ({process:?}).\
                  Please recompile with the 'markdown' feature enabled."
             );
         }
 
         let cx = self.cx;
         let span = tracing::Span::current();
+        // `paginate_by`/`repeat` pages are generated from a collection of
+        // sibling files, so they're aggregate regardless of whether their
+        // template actually reads `page.paginator`/`page.repeat`; whether a
+        // page called `get_file`/`get_files`/`load_data`/`get_taxonomy` is
+        // only known once rendering it has actually run `hinoki_cx`'s
+        // template functions, hence checking `used_aggregate` after `render`
+        // returns rather than up front in `record_and_check_up_to_date`.
+        let is_static_aggregate = file_meta.paginator.is_some() || file_meta.repeat.is_some();
+        let hinoki_cx = file_meta.hinoki_cx.clone();
 
         self.render_scope.spawn(move |_| {
             let _guard = span.enter();
 
-            if let Err(e) = render(file_meta, input_file, cx, content_path) {
-                error!("{e:#}");
-                cx.did_error.store(true, Ordering::Relaxed);
+            match render(file_meta, input_file, cx, content_path) {
+                Ok(()) => {
+                    if let Some(incremental) = &cx.incremental
+                        && (is_static_aggregate || hinoki_cx.used_aggregate())
+                    {
+                        incremental.new_manifest.mark_aggregate(&cache_key);
+                    }
+                }
+                Err(e) => {
+                    error!("{e:#}");
+                    cx.did_error.store(true, Ordering::Relaxed);
+                }
             }
         });
 
         Ok(())
     }
+
+    /// Generates a listing page and one per-term page for every taxonomy
+    /// configured in `config.taxonomies`, and makes the aggregated taxonomies
+    /// available to `get_taxonomy` for the rest of the templates.
+    fn render_taxonomies(&self, root: &DirectoryMetadata) -> anyhow::Result<()> {
+        self.cx.template_global_cx.set_taxonomies(taxonomies::collect(root, &self.cx.taxonomies));
+
+        if self.cx.taxonomies.is_empty() {
+            return Ok(());
+        }
+
+        let by_taxonomy = self.cx.template_global_cx.taxonomies();
+        for taxonomy in &self.cx.taxonomies {
+            let Some(terms) = by_taxonomy.get(taxonomy) else { continue };
+
+            let term_list: Vec<_> = terms
+                .iter()
+                .map(|(term, pages)| TermListEntry {
+                    term: term.clone(),
+                    slug: taxonomies::slugify(term),
+                    count: pages.len(),
+                })
+                .collect();
+
+            self.render_taxonomy_list(taxonomy, &term_list)
+                .with_context(|| format!("rendering taxonomy `{taxonomy}`"))?;
+
+            for (term, pages) in terms {
+                self.render_taxonomy_term(taxonomy, term, pages)
+                    .with_context(|| format!("rendering taxonomy term `{taxonomy}/{term}`"))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn render_taxonomy_list(&self, taxonomy: &str, terms: &[TermListEntry]) -> anyhow::Result<()> {
+        let template = self.cx.template_env.get_template("taxonomy_list.html")?;
+        let rendered = template.render(context! { taxonomy, terms })?;
+
+        let output_path = Utf8Path::new(taxonomy).join("index.html");
+        self.write_taxonomy_page(&output_path, &rendered)
+    }
+
+    fn render_taxonomy_term(
+        &self,
+        taxonomy: &str,
+        term: &str,
+        pages: &[FileMetadata],
+    ) -> anyhow::Result<()> {
+        let template = self.cx.template_env.get_template("taxonomy_single.html")?;
+        let rendered = template.render(context! { taxonomy, term, pages })?;
+
+        let output_path = Utf8Path::new(taxonomy).join(taxonomies::slugify(term)).join("index.html");
+        self.write_taxonomy_page(&output_path, &rendered)
+    }
+
+    fn write_taxonomy_page(&self, output_path: &Utf8Path, content: &str) -> anyhow::Result<()> {
+        let full_path = self.cx.output_path(output_path, output_path)?;
+        fs::write(full_path, content)?;
+        Ok(())
+    }
 }
 
 pub(crate) struct ContentProcessorContext<'a> {
@@ -408,6 +828,19 @@ pub(crate) struct ContentProcessorContext<'a> {
     template_env: minijinja::Environment<'a>,
     template_global_cx: GlobalContext,
     output_dir_mgr: &'a OutputDirManager,
+    taxonomies: Vec<String>,
+    /// If set, only content files whose (absolute) content path is in this
+    /// set are re-rendered; everything else still has its metadata computed
+    /// (since other pages may depend on it) but is skipped for rendering.
+    /// Used to restrict a rebuild to the files affected by a filesystem
+    /// change. `None` means render everything, as in a full build.
+    render_only: Option<HashSet<Utf8PathBuf>>,
+    /// Set for a full, non-`--force` build: lets `dispatch_render` skip a
+    /// page whose content, assigned template, and config are all unchanged
+    /// since the cached manifest was written. `None` for `--force` builds,
+    /// `rebuild_changed`'s dev-server rebuilds (which already have
+    /// `render_only` for that), and `dump`.
+    incremental: Option<IncrementalCache<'a>>,
     pub(crate) did_error: AtomicBool,
 }
 
@@ -418,8 +851,11 @@ impl<'a> ContentProcessorContext<'a> {
         template_env: minijinja::Environment<'a>,
         output_dir_mgr: &'a OutputDirManager,
         template_global_cx: GlobalContext,
+        render_only: Option<HashSet<Utf8PathBuf>>,
+        incremental: Option<IncrementalCache<'a>>,
     ) -> Self {
         let content_dir = config.content_dir();
+        let taxonomies = config.taxonomies.clone();
         Self {
             config,
             content_dir,
@@ -427,6 +863,9 @@ impl<'a> ContentProcessorContext<'a> {
             template_env,
             template_global_cx,
             output_dir_mgr,
+            taxonomies,
+            render_only,
+            incremental,
             did_error: AtomicBool::new(false),
         }
     }
@@ -440,12 +879,51 @@ impl<'a> ContentProcessorContext<'a> {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(crate) struct DirectoryMetadata {
     pub subdirs: Arc<BTreeMap<String, DirectoryMetadata>>,
     pub files: Arc<OnceLock<Vec<FileMetadata>>>,
 }
 
+impl Serialize for DirectoryMetadata {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        // `files` is always populated by the time a `DirectoryMetadata` is
+        // handed out (see `DirectoryContext::into_metadata`), so this only
+        // fails to serialize a tree that was never fully built.
+        let files = self.files.get().expect("files must be set before serializing");
+
+        let mut state = serializer.serialize_struct("DirectoryMetadata", 2)?;
+        state.serialize_field("subdirs", &*self.subdirs)?;
+        state.serialize_field("files", files)?;
+        state.end()
+    }
+}
+
+/// Replaces the subtree at `path_components` (directory names relative to
+/// the content dir, outermost first) within `root` with `new_subtree`,
+/// cloning only the `subdirs` map at each level on the way down to it
+/// instead of the whole tree. A directory named in `path_components` that
+/// `root` doesn't have an entry for yet (created since `root` was built) is
+/// treated as having been empty.
+fn splice_subtree(
+    root: &DirectoryMetadata,
+    path_components: &[String],
+    new_subtree: DirectoryMetadata,
+) -> DirectoryMetadata {
+    let Some((name, rest)) = path_components.split_first() else {
+        return new_subtree;
+    };
+
+    let mut subdirs = (*root.subdirs).clone();
+    let child = subdirs.remove(name.as_str()).unwrap_or_else(|| DirectoryMetadata {
+        subdirs: Arc::new(BTreeMap::new()),
+        files: Arc::new(OnceLock::new()),
+    });
+    subdirs.insert(name.clone(), splice_subtree(&child, rest, new_subtree));
+
+    DirectoryMetadata { subdirs: Arc::new(subdirs), files: root.files.clone() }
+}
+
 #[derive(Clone, Debug, Serialize)]
 pub(crate) struct FileMetadata {
     pub draft: bool,
@@ -455,7 +933,21 @@ pub(crate) struct FileMetadata {
     pub title: Option<Arc<str>>,
     pub date: Option<HinokiDatetime>,
     pub repeat: Option<minijinja::Value>,
+    pub paginator: Option<minijinja::Value>,
+    /// Language code of this page, e.g. `"en"`. Always set, falling back to
+    /// `[languages] default` if neither the frontmatter `lang` field nor a
+    /// `.<lang>` filename segment specified one.
+    pub lang: Arc<str>,
+    /// Other language versions of this page, keyed by language code,
+    /// including `lang` itself. Populated for pages sharing a
+    /// language-stripped source filename within the same directory; empty
+    /// for pages with no known translations.
+    #[serde(serialize_with = "serialize_translations")]
+    pub translations: IndexMap<String, Utf8PathBuf>,
     pub extra: IndexMap<String, toml::Value>,
+    pub taxonomies: IndexMap<String, Vec<String>>,
+    /// Used by `[sort] by = "weight"` to order this page among its siblings.
+    pub weight: Option<i64>,
 
     // further data from frontmatter that should be printed in dump-metadata
     // but not passed to the template as `page.*`
@@ -468,6 +960,23 @@ pub(crate) struct FileMetadata {
 }
 
 fn serialize_path<S: Serializer>(path: &Utf8Path, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&format_output_path(path))
+}
+
+fn serialize_translations<S: Serializer>(
+    translations: &IndexMap<String, Utf8PathBuf>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    use serde::ser::SerializeMap as _;
+
+    let mut map = serializer.serialize_map(Some(translations.len()))?;
+    for (lang, path) in translations {
+        map.serialize_entry(lang, &format_output_path(path))?;
+    }
+    map.end()
+}
+
+fn format_output_path(path: &Utf8Path) -> String {
     // Print with '/' as separator, even on Windows.
     let mut s = format!("/{}", path.iter().format("/"));
     // path.iter() does not return an empty final segment if the path ends in
@@ -475,19 +984,33 @@ fn serialize_path<S: Serializer>(path: &Utf8Path, serializer: S) -> Result<S::Ok
     if path.as_str().ends_with("/") {
         s.push('/');
     }
-    serializer.serialize_str(&s)
+    s
 }
 
 #[derive(Clone, Debug, Serialize)]
 pub(crate) struct RepeatFileMetadata {
     pub draft: bool,
     pub slug: String,
+    #[serde(serialize_with = "serialize_path")]
     pub path: Utf8PathBuf,
     pub title: Option<String>,
     pub date: Option<HinokiDatetime>,
     pub extra: IndexMap<String, toml::Value>,
 }
 
+impl From<&FileMetadata> for RepeatFileMetadata {
+    fn from(meta: &FileMetadata) -> Self {
+        Self {
+            draft: meta.draft,
+            slug: meta.slug.to_string(),
+            path: meta.path.to_path_buf(),
+            title: meta.title.as_ref().map(ToString::to_string),
+            date: meta.date,
+            extra: meta.extra.clone(),
+        }
+    }
+}
+
 #[derive(Clone, Debug, Serialize)]
 pub(crate) struct Repeat {
     /// The current item.
@@ -499,6 +1022,27 @@ pub(crate) struct Repeat {
     // TODO: maybe this struct should actually be a custom minijinja Object?
 }
 
+#[derive(Clone, Debug, Serialize)]
+pub(crate) struct Paginator {
+    /// 1-indexed page number, for use in templates (`page/{{ paginator.number }}/`).
+    number: usize,
+    current_index: usize,
+    total_pages: usize,
+    /// This page's slice of the directory's non-paginating sibling files.
+    files: Vec<FileMetadata>,
+    #[serde(serialize_with = "serialize_opt_path")]
+    prev_page_path: Option<Arc<Utf8Path>>,
+    #[serde(serialize_with = "serialize_opt_path")]
+    next_page_path: Option<Arc<Utf8Path>>,
+}
+
+fn serialize_opt_path<S: Serializer>(
+    path: &Option<Arc<Utf8Path>>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    path.as_deref().map(format_output_path).serialize(serializer)
+}
+
 fn render(
     file_meta: FileMetadata,
     mut input_file: BufReader<File>,
@@ -511,6 +1055,14 @@ fn render(
         .map(|tpl| cx.template_env.get_template(tpl.as_str()))
         .transpose()?;
 
+    if template.is_none() && file_meta.process.is_some() {
+        anyhow::bail!(
+            "`{}` sets `process` but has no `template`; the processed content has nowhere to be \
+             rendered into",
+            file_meta.path,
+        );
+    }
+
     let output_path = cx.output_path(&file_meta.path, &content_path)?;
     let mut output_file = BufWriter::new(File::create(output_path)?);
 
@@ -527,16 +1079,38 @@ fn render(
     let hinoki_cx = &file_meta.hinoki_cx;
 
     #[cfg(feature = "markdown")]
-    if let Some(ProcessContent::MarkdownToHtml) = file_meta.process {
-        content = markdown_to_html(&content, hinoki_cx)?;
+    let mut toc = Vec::new();
+
+    #[cfg(feature = "markdown")]
+    if let Some(process) = &file_meta.process {
+        let (html, headings) = process.process(&content, hinoki_cx, &cx.template_env)?;
+        content = html;
+        toc = headings;
     }
 
+    let minify_html_enabled = cx.config.minify_html;
+
     if let Some(template) = template {
         let extra = &cx.config.extra;
-        let cx =
-            TemplateContext { content, page: &file_meta, config: context! { extra }, hinoki_cx };
+        let tpl_cx = TemplateContext {
+            content,
+            page: PageContext {
+                meta: &file_meta,
+                #[cfg(feature = "markdown")]
+                toc,
+            },
+            config: context! { extra },
+            hinoki_cx,
+        };
 
-        template.render_to_write(cx, output_file)?;
+        if minify_html_enabled {
+            let rendered = template.render(tpl_cx)?;
+            output_file.write_all(minify(rendered).as_bytes())?;
+        } else {
+            template.render_to_write(tpl_cx, output_file)?;
+        }
+    } else if minify_html_enabled {
+        output_file.write_all(minify(content).as_bytes())?;
     } else {
         output_file.write_all(content.as_bytes())?;
     }
@@ -544,13 +1118,26 @@ fn render(
     Ok(())
 }
 
+#[cfg(feature = "minify-html")]
+fn minify(html: String) -> String {
+    self::minify::minify_html(&html)
+}
+
+#[cfg(not(feature = "minify-html"))]
+fn minify(html: String) -> String {
+    html
+}
+
 #[derive(Clone, Debug)]
 struct MetadataContext {
     source_path: Arc<Utf8Path>,
+    lang: Arc<str>,
     slug: Option<Arc<str>>,
     title: Option<Arc<str>>,
     date: Option<HinokiDatetime>,
+    weight: Option<i64>,
     repeat: Option<minijinja::Value>,
+    paginator: Option<minijinja::Value>,
 }
 
 impl MetadataContext {
@@ -572,13 +1159,16 @@ impl Object for MetadataContext {
         match key.as_str()? {
             "source_dir" => Some(self.source_dir()),
             "source_file_stem" => Some(self.source_file_stem().into()),
+            "lang" => Some(self.lang.clone().into()),
             "slug" => self.slug.clone().map(Into::into),
             "title" => self.title.clone().map(Into::into),
             "date" => self.date.map(minijinja::Value::from_serialize),
             "year" => self.date.map(|d| format!("{:04}", d.date.year).into()),
             "month" => self.date.map(|d| format!("{:02}", d.date.month).into()),
             "day" => self.date.map(|d| format!("{:02}", d.date.day).into()),
+            "weight" => self.weight.map(Into::into),
             "repeat" => self.repeat.clone(),
+            "paginator" => self.paginator.clone(),
             _ => None,
         }
     }
@@ -589,3 +1179,117 @@ enum WriteOutput {
     Yes,
     No,
 }
+
+/// A content file's computed metadata, along with what's needed to render it
+/// afterwards. Kept separate from rendering so that an entire directory's
+/// metadata is available (for `attach_translations`) before any of it is
+/// rendered.
+struct PendingFile {
+    content_path: Utf8PathBuf,
+    /// Byte offset of the end of the frontmatter block, i.e. where the page
+    /// content starts.
+    frontmatter_end: u64,
+    /// The file's source path with any `.<lang>` filename segment left in
+    /// place, unique per on-disk file; used as the incremental cache key.
+    cache_key: Utf8PathBuf,
+    /// The file's source path with any `.<lang>` filename segment stripped,
+    /// used to group files that are translations of each other.
+    translation_group_key: Arc<Utf8Path>,
+    meta: SmallVec<[FileMetadata; 1]>,
+    /// Set instead of populating `meta` when this file's frontmatter has
+    /// `paginate_by` set; expanded into `meta` by `expand_pagination` once
+    /// every other file in the directory has its metadata computed.
+    pagination: Option<PendingPagination>,
+}
+
+/// Everything needed to render a `paginate_by` file's pages, once the rest of
+/// the directory's sibling files are known.
+struct PendingPagination {
+    source_path: Arc<Utf8Path>,
+    lang: Arc<str>,
+    dir_cx: DirectoryContext,
+    frontmatter: ContentFileConfig,
+    per_page: usize,
+}
+
+/// Strips a trailing `.<lang>` segment matching a configured language code
+/// from `path`'s file stem, returning the stripped path and the language
+/// code found, e.g. `posts/about.fr.md` -> (`posts/about.md`, `Some("fr")`).
+///
+/// Returns `path` unchanged (and `None`) if the stem has no such segment.
+fn split_language_suffix(
+    path: &Utf8Path,
+    languages: &LanguagesConfig,
+) -> (Arc<Utf8Path>, Option<String>) {
+    let stem = path.file_stem().unwrap_or_default();
+    let Some((base_stem, suffix)) = stem.rsplit_once('.') else {
+        return (path.into(), None);
+    };
+
+    if suffix != languages.default && !languages.others.iter().any(|lang| lang == suffix) {
+        return (path.into(), None);
+    }
+
+    let file_name = match path.extension() {
+        Some(ext) => format!("{base_stem}.{ext}"),
+        None => base_stem.to_owned(),
+    };
+    (path.with_file_name(file_name).as_path().into(), Some(suffix.to_owned()))
+}
+
+/// Orders `files` per `[sort] by`/`reverse`, falling back to ascending
+/// `slug` order on ties (including every file, for [`SortKey::None`]), so
+/// the result is reproducible regardless of the filesystem's `read_dir`
+/// order.
+fn sort_files(files: &mut [FileMetadata], sort: &SortConfig) {
+    files.sort_by(|a, b| {
+        let primary = match sort.by {
+            SortKey::None => CmpOrdering::Equal,
+            SortKey::Date => missing_last(&a.date, &b.date),
+            SortKey::Title => missing_last(&a.title, &b.title),
+            SortKey::Slug => a.slug.cmp(&b.slug),
+            SortKey::Weight => missing_last(&a.weight, &b.weight),
+        };
+
+        let ordering = primary.then_with(|| a.slug.cmp(&b.slug));
+        if sort.reverse { ordering.reverse() } else { ordering }
+    });
+}
+
+/// Like `Option::cmp`, but treats `None` as greater than any `Some`, so
+/// files missing the sort key end up last regardless of `reverse`.
+fn missing_last<T: Ord>(a: &Option<T>, b: &Option<T>) -> CmpOrdering {
+    match (a, b) {
+        (Some(a), Some(b)) => a.cmp(b),
+        (Some(_), None) => CmpOrdering::Less,
+        (None, Some(_)) => CmpOrdering::Greater,
+        (None, None) => CmpOrdering::Equal,
+    }
+}
+
+/// Groups `pending` by [`PendingFile::translation_group_key`] and records
+/// every group member's final output path on every other member's
+/// `FileMetadata::translations`, so templates can render a language
+/// switcher. Pages produced via `repeat` are excluded, since pagination
+/// isn't a translation relationship.
+fn attach_translations(pending: &mut [PendingFile]) {
+    let mut groups: IndexMap<Arc<Utf8Path>, Vec<(Arc<str>, Arc<Utf8Path>)>> = IndexMap::new();
+    for pending_file in pending.iter() {
+        let [file_meta] = &pending_file.meta[..] else { continue };
+        groups
+            .entry(pending_file.translation_group_key.clone())
+            .or_default()
+            .push((file_meta.lang.clone(), file_meta.path.clone()));
+    }
+
+    for pending_file in pending.iter_mut() {
+        let [file_meta] = &mut pending_file.meta[..] else { continue };
+        let Some(group) = groups.get(&pending_file.translation_group_key) else { continue };
+        if group.len() < 2 {
+            continue;
+        }
+
+        file_meta.translations =
+            group.iter().map(|(lang, path)| (lang.to_string(), path.to_path_buf())).collect();
+    }
+}
@@ -21,9 +21,22 @@ pub(crate) struct ContentFileConfig {
     pub process: Option<ProcessContent>,
 
     /// Syntax highlighting theme for markdown code blocks.
+    ///
+    /// The special value `"css"` selects class-based output for this page
+    /// regardless of the site-wide `[syntax_highlight] style`, for use with a
+    /// generated stylesheet (see `Build::write_syntax_stylesheets`).
     pub syntax_highlight_theme: Option<String>,
 
     /// Custom rendered path for this page.
+    ///
+    /// A minijinja expression (`{{ ... }}` delimited, e.g.
+    /// `"/{{ year }}/{{ month }}/{{ slug }}/"`) with `source_dir`,
+    /// `source_file_stem`, `slug`, `title`, `date` (plus the derived `year`,
+    /// `month`, `day`), `weight`, `repeat` and `paginator` available,
+    /// evaluated after those fields are themselves resolved. Set per glob in
+    /// `[content."<glob>"]` to give a whole directory a permalink pattern
+    /// decoupled from its source layout; defaults to the page's source path
+    /// when unset. Two pages resolving to the same path is a build error.
     pub path: Option<String>,
 
     /// Page title.
@@ -35,6 +48,14 @@ pub(crate) struct ContentFileConfig {
     /// Custom slug for this page, to replace the file basename.
     pub slug: Option<String>,
 
+    /// Language of this page, as one of the codes configured in
+    /// `[languages]`.
+    ///
+    /// If unset, the language is inferred from a `.<lang>` segment in the
+    /// source filename (e.g. `about.fr.md`), falling back to the site's
+    /// default language.
+    pub lang: Option<String>,
+
     /// Render this page once for each item in the iterator.
     ///
     /// The string must be a minijinja expression that evaluates to an iterator.
@@ -42,6 +63,26 @@ pub(crate) struct ContentFileConfig {
     /// For example: `get_files("directory") | chunks(10)`.
     pub repeat: Option<String>,
 
+    /// Split this page into multiple output pages, each given a fixed-size
+    /// slice of this page's sibling files in the same content directory.
+    ///
+    /// Each generated page exposes a `paginator` object (current page number,
+    /// total page count, this page's slice of files, and prev/next page
+    /// paths) to templates and to the `path`/`slug`/`title` expressions on
+    /// this page, e.g. `path = "/blog/page/{{ paginator.number }}/"`.
+    /// Mutually exclusive with `repeat`.
+    pub paginate_by: Option<usize>,
+
+    /// Used by `[sort] by = "weight"` to order this page among its siblings,
+    /// ascending. Pages without a `weight` sort after those with one.
+    pub weight: Option<i64>,
+
+    /// Terms this page belongs to, keyed by taxonomy name.
+    ///
+    /// For example: `tags = ["rust", "web"]`.
+    #[serde(default)]
+    pub taxonomies: IndexMap<String, Vec<String>>,
+
     /// Arbitrary additional user-defined data.
     #[serde(default)]
     pub extra: IndexMap<String, toml::Value>,
@@ -63,7 +104,7 @@ impl ContentFileConfig {
             self.template = config.template.clone();
         }
         if self.process.is_none() {
-            self.process = config.process;
+            self.process = config.process.clone();
         }
         if self.syntax_highlight_theme.is_none() {
             self.syntax_highlight_theme = config.syntax_highlight_theme.clone();
@@ -80,9 +121,21 @@ impl ContentFileConfig {
         if self.slug.is_none() {
             self.slug = config.slug.clone();
         }
+        if self.lang.is_none() {
+            self.lang = config.lang.clone();
+        }
         if self.repeat.is_none() {
             self.repeat = config.repeat.clone();
         }
+        if self.paginate_by.is_none() {
+            self.paginate_by = config.paginate_by;
+        }
+        if self.weight.is_none() {
+            self.weight = config.weight;
+        }
+        for (taxonomy, terms) in &config.taxonomies {
+            self.taxonomies.entry(taxonomy.to_owned()).or_insert_with(|| terms.clone());
+        }
         apply_extra_defaults(&mut self.extra, &config.extra);
     }
 }
@@ -119,8 +172,18 @@ fn apply_inner_extra_defaults(target: &mut toml::Value, source: &toml::Value) {
     }
 }
 
-#[derive(Clone, Copy, Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub(crate) enum ProcessContent {
     MarkdownToHtml,
+    /// Render [reStructuredText](https://docutils.sourceforge.io/rst.html) to HTML.
+    RstToHtml,
+    /// Pipe the raw content through an external program's stdin, and use its
+    /// stdout as the rendered HTML. Lets a site use a markup format hinoki
+    /// has no built-in support for (e.g. AsciiDoc via `asciidoctor`).
+    Command {
+        program: String,
+        #[serde(default)]
+        args: Vec<String>,
+    },
 }
@@ -0,0 +1,200 @@
+//! Post-build validation of links in the generated HTML output, gated by
+//! `[link_check] enabled` so fast iterative builds can skip it.
+//!
+//! Internal (site-relative) links, including `#id` anchors, are checked
+//! against the set of output paths the build actually produced (see
+//! [`OutputDirManager::output_paths`]) and the heading ids emitted by the TOC
+//! extraction (see `toc::extract_toc`) — purely in-memory/on-disk, no
+//! network involved. External `http(s)` links are additionally checked over
+//! the network when `[link_check] external` is set, behind the
+//! `link-checking` feature; a broken external link is only ever a warning,
+//! never a build failure.
+
+#[cfg(feature = "link-checking")]
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+use camino::{Utf8Path, Utf8PathBuf};
+use fs_err as fs;
+use tracing::warn;
+
+use crate::{build::OutputDirManager, config::LinkCheckConfig};
+
+/// Scans every HTML file written during the build for `href`/`src` targets
+/// and reports broken internal links (including dangling `#id` anchors),
+/// plus broken external links if `config.external` is set.
+///
+/// Returns `true` if any *internal* link is broken; the caller folds that
+/// into `did_error`. Broken external links are only logged.
+pub(crate) fn check_links(
+    output_dir_mgr: &OutputDirManager,
+    config: &LinkCheckConfig,
+) -> anyhow::Result<bool> {
+    let output_paths = output_dir_mgr.output_paths();
+    let mut has_broken_links = false;
+    let mut external_links = HashSet::new();
+
+    for output_path in &output_paths {
+        if output_path.extension() != Some("html") {
+            continue;
+        }
+
+        let html = fs::read_to_string(output_path)?;
+        for link in extract_links(&html) {
+            match classify(link) {
+                Link::Internal(link) => {
+                    let (target, fragment) = split_link(link);
+                    let resolved = resolve_internal(&output_dir_mgr.output_dir, output_path, target);
+                    if !output_paths.contains(&resolved) {
+                        warn!("Broken internal link `{link}` in `{output_path}`");
+                        has_broken_links = true;
+                        continue;
+                    }
+
+                    let Some(id) = fragment.filter(|id| !id.is_empty()) else { continue };
+                    if !fs::read_to_string(&resolved)?.contains(&format!("id=\"{id}\"")) {
+                        warn!("Broken anchor `#{id}` in link `{link}` in `{output_path}`");
+                        has_broken_links = true;
+                    }
+                }
+                Link::External(url) if config.external => {
+                    external_links.insert((url.to_owned(), output_path.clone()));
+                }
+                Link::External(_) | Link::Other => {}
+            }
+        }
+    }
+
+    #[cfg(feature = "link-checking")]
+    if config.external {
+        check_external_links(external_links, &config.skip_domains);
+    }
+    #[cfg(not(feature = "link-checking"))]
+    if config.external && !external_links.is_empty() {
+        warn!(
+            "Checking external links was requested, but hinoki was compiled\
+             without support for it. Please recompile with the 'link-checking'\
+             feature enabled."
+        );
+    }
+
+    Ok(has_broken_links)
+}
+
+enum Link<'a> {
+    Internal(&'a str),
+    External(&'a str),
+    /// `mailto:`, `tel:`, or some other scheme we don't validate.
+    Other,
+}
+
+/// Classifies a raw `href`/`src` value as a site-relative/page-relative/
+/// same-page (`#id`) internal link, an `http(s)://` (including
+/// protocol-relative `//`) external link, or neither.
+fn classify(link: &str) -> Link<'_> {
+    if link.is_empty() || link.starts_with("mailto:") || link.starts_with("tel:") {
+        Link::Other
+    } else if link.starts_with("http://") || link.starts_with("https://") || link.starts_with("//") {
+        Link::External(link)
+    } else if !link.starts_with('#') && link.contains("://") {
+        Link::Other
+    } else {
+        Link::Internal(link)
+    }
+}
+
+/// Splits off the query string and fragment from a link target, e.g.
+/// `"/a/b?x=1#c"` -> `("/a/b", Some("c"))`.
+fn split_link(link: &str) -> (&str, Option<&str>) {
+    let link = link.split('?').next().unwrap_or(link);
+    match link.split_once('#') {
+        Some((path, fragment)) => (path, Some(fragment)),
+        None => (link, None),
+    }
+}
+
+/// Resolves a site-relative, page-relative, or same-page (empty) link target
+/// to the output path it should correspond to, mirroring
+/// [`OutputDirManager::output_path`]'s handling of directory (trailing-slash)
+/// links.
+fn resolve_internal(
+    output_dir: &Utf8Path,
+    referencing_page: &Utf8Path,
+    target: &str,
+) -> Utf8PathBuf {
+    if target.is_empty() {
+        return referencing_page.to_owned();
+    }
+
+    let mut resolved = match target.strip_prefix('/') {
+        Some(site_relative) => output_dir.join(site_relative),
+        None => {
+            let dir = referencing_page.parent().unwrap_or(Utf8Path::new(""));
+            dir.join(target)
+        }
+    };
+
+    if resolved.as_str().ends_with('/') {
+        resolved.push("index.html");
+    }
+
+    resolved
+}
+
+/// Checks every distinct external URL exactly once (deduplicated across
+/// pages that link to the same one), skipping `skip_domains`, and warns for
+/// each one that's unreachable, against every page that referenced it.
+/// Never fails the build: external sites are outside hinoki's control and
+/// can be down or rate-limiting temporarily.
+#[cfg(feature = "link-checking")]
+fn check_external_links(links: HashSet<(String, Utf8PathBuf)>, skip_domains: &[String]) {
+    use rayon::iter::{IntoParallelIterator as _, ParallelIterator as _};
+
+    let mut referencing_pages: HashMap<String, Vec<Utf8PathBuf>> = HashMap::new();
+    for (url, referencing_page) in links {
+        referencing_pages.entry(url).or_default().push(referencing_page);
+    }
+
+    let client = reqwest::blocking::Client::builder()
+        .timeout(std::time::Duration::from_secs(10))
+        .build()
+        .expect("building the external link checker's HTTP client");
+
+    referencing_pages.into_par_iter().for_each(|(url, pages)| {
+        let host = url.trim_start_matches("//").split("://").last().unwrap_or("");
+        let host = host.split('/').next().unwrap_or("");
+        if skip_domains.iter().any(|domain| domain == host) {
+            return;
+        }
+
+        // Protocol-relative links (`//host/path`) need an explicit scheme to
+        // be a valid request URL.
+        let request_url = if let Some(rest) = url.strip_prefix("//") {
+            format!("https://{rest}")
+        } else {
+            url.clone()
+        };
+
+        let ok = client.head(&request_url).send().is_ok_and(|resp| resp.status().is_success())
+            || client.get(&request_url).send().is_ok_and(|resp| resp.status().is_success());
+        if !ok {
+            for referencing_page in &pages {
+                warn!("Broken external link `{url}` (referenced from `{referencing_page}`)");
+            }
+        }
+    });
+}
+
+/// Finds every `href="..."`/`src="..."` attribute value in `html`.
+///
+/// This is a plain substring scan rather than a full HTML parse: good enough
+/// to find link targets, and avoids pulling in an HTML parser just for this.
+fn extract_links(html: &str) -> impl Iterator<Item = &str> {
+    ["href=\"", "src=\""].into_iter().flat_map(move |needle| {
+        html.match_indices(needle).filter_map(move |(idx, _)| {
+            let start = idx + needle.len();
+            let end = html[start..].find('"')? + start;
+            Some(&html[start..end])
+        })
+    })
+}
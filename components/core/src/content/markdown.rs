@@ -1,21 +1,140 @@
-use pulldown_cmark::{html::push_html, Options, Parser};
+use pulldown_cmark::{html::push_html, CowStr, Event, Parser, Tag, TagEnd};
 
-use crate::template::context::HinokiContext;
+use super::{shortcodes::expand_shortcodes, toc::extract_toc, TocEntry};
+#[cfg(feature = "syntax-highlighting")]
+use crate::config::SyntaxHighlightStyle;
+use crate::{config::MarkdownConfig, template::context::HinokiContext};
 
-pub(crate) fn markdown_to_html(content: &str, hinoki_cx: &HinokiContext) -> anyhow::Result<String> {
+/// Frontmatter `syntax_highlight_theme` sentinel that selects class-based
+/// output (see [`SyntaxHighlightStyle::Classed`]) instead of naming an actual
+/// theme, so a single page can opt into a CSS-driven stylesheet regardless of
+/// the site-wide default style.
+#[cfg(feature = "syntax-highlighting")]
+const CLASSED_THEME_SENTINEL: &str = "css";
+
+pub(crate) fn markdown_to_html(
+    content: &str,
+    hinoki_cx: &HinokiContext,
+    template_env: &minijinja::Environment<'_>,
+) -> anyhow::Result<(String, Vec<TocEntry>)> {
     #[cfg(feature = "syntax-highlighting")]
     let syntax_highlighter = hinoki_cx.syntax_highlighter()?;
 
-    let parser = Parser::new_ext(content, Options::ENABLE_FOOTNOTES);
+    let content = expand_shortcodes(content, template_env)?;
+
+    let markdown_config = hinoki_cx.markdown_config();
+    let parser = Parser::new_ext(&content, markdown_config.pulldown_options());
+    let events = rewrite_external_links(parser, markdown_config, hinoki_cx.base_url());
+    let (events, toc) = extract_toc(events);
+    let events = events.into_iter();
+
     let mut html_buf = String::new();
 
     #[cfg(feature = "syntax-highlighting")]
-    if let Some(theme) = hinoki_cx.syntax_highlight_theme().or_else(|| syntax_highlighter.theme()) {
-        let with_highlighting = syntax_highlighter.highlight(parser, theme)?;
-        push_html(&mut html_buf, with_highlighting);
-    } else {
-        push_html(&mut html_buf, parser);
+    {
+        let theme_override = hinoki_cx.syntax_highlight_theme();
+        let use_classed = theme_override == Some(CLASSED_THEME_SENTINEL);
+        let theme_override = if use_classed { None } else { theme_override };
+        let theme = theme_override.or_else(|| syntax_highlighter.theme());
+
+        if let Some(theme) = theme {
+            let style =
+                if use_classed { SyntaxHighlightStyle::Classed } else { hinoki_cx.syntax_highlight_style() };
+            let with_highlighting = syntax_highlighter.highlight(events, theme, style)?;
+            push_html(&mut html_buf, with_highlighting);
+        } else {
+            push_html(&mut html_buf, events);
+        }
+    }
+
+    #[cfg(not(feature = "syntax-highlighting"))]
+    push_html(&mut html_buf, events);
+
+    Ok((html_buf, toc))
+}
+
+/// Rewrite links to external sites to carry `target`/`rel` attributes, as
+/// configured in `[markdown]`.
+///
+/// Links are considered external if their destination is an absolute URL
+/// whose scheme and host don't match `base_url`. If `base_url` isn't set,
+/// any absolute URL is considered external.
+fn rewrite_external_links<'a>(
+    events: impl Iterator<Item = Event<'a>>,
+    config: &MarkdownConfig,
+    base_url: Option<&str>,
+) -> impl Iterator<Item = Event<'a>> {
+    let mut rel = String::new();
+    if config.external_links_no_follow {
+        rel.push_str("nofollow");
+    }
+    if config.external_links_no_referrer {
+        if !rel.is_empty() {
+            rel.push(' ');
+        }
+        rel.push_str("noreferrer");
     }
 
-    Ok(html_buf)
+    let mut in_external_link = false;
+
+    events.filter_map(move |event| match event {
+        Event::Start(Tag::Link { dest_url, title, .. })
+            if config.rewrites_external_links() && is_external(&dest_url, base_url) =>
+        {
+            let mut tag = format!(r#"<a href="{}""#, escape_html_attribute(&dest_url));
+            if !title.is_empty() {
+                tag.push_str(&format!(r#" title="{}""#, escape_html_attribute(&title)));
+            }
+            if config.external_links_target_blank {
+                tag.push_str(r#" target="_blank""#);
+            }
+            if !rel.is_empty() {
+                tag.push_str(&format!(r#" rel="{rel}""#));
+            }
+            tag.push('>');
+
+            in_external_link = true;
+            Some(Event::Html(CowStr::from(tag)))
+        }
+        Event::End(TagEnd::Link) if in_external_link => {
+            in_external_link = false;
+            Some(Event::Html(CowStr::from("</a>")))
+        }
+        ev => Some(ev),
+    })
+}
+
+/// Escapes `&`, `"`, `<` and `>` so `value` can be safely spliced into a
+/// double-quoted HTML attribute. Needed because the link's destination and
+/// title come straight from the Markdown source and, unlike
+/// [`pulldown_cmark::html::push_html`]'s own output, aren't escaped before
+/// we hand-build the `<a>` tag as raw HTML.
+fn escape_html_attribute(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for ch in value.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '"' => escaped.push_str("&quot;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            _ => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+fn is_external(dest_url: &str, base_url: Option<&str>) -> bool {
+    let Some((scheme, rest)) = dest_url.split_once("://") else {
+        // Relative URLs, fragments, mailto:, etc. are never external.
+        return false;
+    };
+    let host = rest.split(['/', '?', '#']).next().unwrap_or(rest);
+
+    match base_url.and_then(|base| base.split_once("://")) {
+        Some((base_scheme, base_rest)) => {
+            let base_host = base_rest.split(['/', '?', '#']).next().unwrap_or(base_rest);
+            scheme != base_scheme || host != base_host
+        }
+        None => true,
+    }
 }
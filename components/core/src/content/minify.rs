@@ -0,0 +1,11 @@
+/// Minifies rendered HTML: collapses insignificant whitespace, strips
+/// comments, and minifies inline `<style>`/`<script>`.
+///
+/// This is HTML5-aware, so whitespace-significant elements (`<pre>`,
+/// `<textarea>`) and the `<code>` blocks emitted by the syntax highlighter
+/// are left untouched.
+pub(super) fn minify_html(html: &str) -> String {
+    let cfg = minify_html::Cfg { minify_css: true, minify_js: true, ..minify_html::Cfg::new() };
+    let minified = minify_html::minify(html.as_bytes(), &cfg);
+    String::from_utf8(minified).expect("minifier must preserve UTF-8 validity")
+}
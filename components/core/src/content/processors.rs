@@ -0,0 +1,65 @@
+//! Implements each [`ProcessContent`] variant, turning a page's raw source
+//! content into rendered HTML.
+
+use std::{
+    io::Write as _,
+    process::{Command, Stdio},
+};
+
+use anyhow::Context as _;
+
+use super::{file_config::ProcessContent, markdown::markdown_to_html, TocEntry};
+use crate::template::context::HinokiContext;
+
+impl ProcessContent {
+    /// Turns `content` into rendered HTML, along with a table of contents if
+    /// this processor extracts one (currently only Markdown does).
+    pub(crate) fn process(
+        &self,
+        content: &str,
+        hinoki_cx: &HinokiContext,
+        template_env: &minijinja::Environment<'_>,
+    ) -> anyhow::Result<(String, Vec<TocEntry>)> {
+        match self {
+            ProcessContent::MarkdownToHtml => markdown_to_html(content, hinoki_cx, template_env),
+            ProcessContent::RstToHtml => Ok((rst_to_html(content)?, Vec::new())),
+            ProcessContent::Command { program, args } => {
+                Ok((run_command(program, args, content)?, Vec::new()))
+            }
+        }
+    }
+}
+
+fn rst_to_html(content: &str) -> anyhow::Result<String> {
+    let document = rst_parser::parse(content).context("parsing reStructuredText")?;
+
+    let mut html = Vec::new();
+    rst_renderer::render_html(&document, &mut html, false)
+        .context("rendering reStructuredText to HTML")?;
+
+    String::from_utf8(html).context("reStructuredText renderer produced invalid UTF-8")
+}
+
+/// Pipes `content` through `program`'s stdin and captures its stdout as the
+/// rendered HTML.
+fn run_command(program: &str, args: &[String], content: &str) -> anyhow::Result<String> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("spawning `{program}`"))?;
+
+    child
+        .stdin
+        .take()
+        .expect("stdin was requested with Stdio::piped")
+        .write_all(content.as_bytes())
+        .with_context(|| format!("writing to `{program}`'s stdin"))?;
+
+    let output =
+        child.wait_with_output().with_context(|| format!("waiting for `{program}` to exit"))?;
+    anyhow::ensure!(output.status.success(), "`{program}` exited with {}", output.status);
+
+    String::from_utf8(output.stdout).with_context(|| format!("`{program}` produced invalid UTF-8"))
+}
@@ -0,0 +1,191 @@
+//! Shortcode expansion for markdown content.
+//!
+//! Shortcodes are author-defined snippets invoked inline as
+//! `{{ name(key="value") }}` or in block form as
+//! `{% name(key="value") %}...{% endname %}`, rendered through
+//! `templates/shortcodes/<name>.html` using the site's existing MiniJinja
+//! environment. This runs before markdown parsing, so shortcode output is
+//! raw HTML that pulldown-cmark passes through unchanged.
+
+use std::collections::HashMap;
+
+use anyhow::Context as _;
+use minijinja::{value::Value, Environment};
+
+pub(crate) fn expand_shortcodes(content: &str, env: &Environment<'_>) -> anyhow::Result<String> {
+    let mut invocation_counts: HashMap<String, usize> = HashMap::new();
+    let mut output = String::with_capacity(content.len());
+    let mut rest = content;
+
+    while let Some((pos, kind)) = find_next_invocation(rest) {
+        output.push_str(&rest[..pos]);
+
+        let close = kind.close_tag();
+        let (name, args, after) = parse_invocation(&rest[pos..], close)?;
+
+        let body = match kind {
+            InvocationKind::Inline => {
+                rest = after;
+                None
+            }
+            InvocationKind::Block => {
+                let end_tag = format!("{{% end{name} %}}");
+                let end_pos = after
+                    .find(&end_tag)
+                    .with_context(|| format!("missing `{end_tag}` for shortcode `{name}`"))?;
+                rest = &after[end_pos + end_tag.len()..];
+                Some(&after[..end_pos])
+            }
+        };
+
+        let nth = invocation_counts.entry(name.clone()).or_insert(0);
+        let current_nth = *nth;
+        *nth += 1;
+
+        output.push_str(&render_shortcode(env, &name, args, current_nth, body)?);
+    }
+
+    output.push_str(rest);
+    Ok(output)
+}
+
+#[derive(Clone, Copy)]
+enum InvocationKind {
+    /// `{{ name(...) }}`
+    Inline,
+    /// `{% name(...) %}...{% endname %}`
+    Block,
+}
+
+impl InvocationKind {
+    fn close_tag(self) -> &'static str {
+        match self {
+            InvocationKind::Inline => "}}",
+            InvocationKind::Block => "%}",
+        }
+    }
+}
+
+/// Find the next `{{ name(` or `{% name(` occurrence, skipping anything that
+/// doesn't look like a shortcode invocation (e.g. stray `{{` in prose).
+fn find_next_invocation(s: &str) -> Option<(usize, InvocationKind)> {
+    let mut search_from = 0;
+
+    loop {
+        let inline_pos = s[search_from..].find("{{").map(|p| p + search_from);
+        let block_pos = s[search_from..].find("{%").map(|p| p + search_from);
+
+        let (pos, kind) = match (inline_pos, block_pos) {
+            (None, None) => return None,
+            (Some(i), None) => (i, InvocationKind::Inline),
+            (None, Some(b)) => (b, InvocationKind::Block),
+            (Some(i), Some(b)) if i < b => (i, InvocationKind::Inline),
+            (_, Some(b)) => (b, InvocationKind::Block),
+        };
+
+        if looks_like_invocation(&s[pos + 2..]) {
+            return Some((pos, kind));
+        }
+
+        search_from = pos + 2;
+    }
+}
+
+fn looks_like_invocation(s: &str) -> bool {
+    let s = s.trim_start();
+    let name_end = s.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_')).unwrap_or(s.len());
+    name_end > 0 && s[name_end..].starts_with('(')
+}
+
+/// Parses `name(key="value", ...)` starting right after the opening `{{` or
+/// `{%`, up to and including `close` (`}}` or `%}`). Returns the shortcode
+/// name, its keyword arguments, and the remainder of the input.
+fn parse_invocation<'a>(
+    s: &'a str,
+    close: &str,
+) -> anyhow::Result<(String, HashMap<String, Value>, &'a str)> {
+    let end = s.find(close).context("unterminated shortcode tag")?;
+    let inner = s[2..end].trim();
+    let rest = &s[end + close.len()..];
+
+    let paren_start = inner.find('(').context("expected `(` in shortcode invocation")?;
+    let name = inner[..paren_start].trim().to_owned();
+    let args_str = inner[paren_start + 1..]
+        .strip_suffix(')')
+        .context("expected `)` in shortcode invocation")?;
+
+    Ok((name, parse_kwargs(args_str)?, rest))
+}
+
+fn parse_kwargs(s: &str) -> anyhow::Result<HashMap<String, Value>> {
+    let mut args = HashMap::new();
+    for part in split_args(s) {
+        let part = part.trim();
+        if part.is_empty() {
+            continue;
+        }
+
+        let (key, value) = part
+            .split_once('=')
+            .with_context(|| format!("expected `key=value` shortcode argument, found `{part}`"))?;
+        args.insert(key.trim().to_owned(), parse_value(value.trim()));
+    }
+    Ok(args)
+}
+
+/// Splits `a="x, y", b=1` on top-level commas, ignoring commas inside quoted
+/// strings.
+fn split_args(s: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut in_quotes = false;
+    let mut last = 0;
+
+    for (i, b) in s.bytes().enumerate() {
+        match b {
+            b'"' => in_quotes = !in_quotes,
+            b',' if !in_quotes => {
+                parts.push(&s[last..i]);
+                last = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&s[last..]);
+    parts
+}
+
+fn parse_value(s: &str) -> Value {
+    if let Some(unquoted) = s.strip_prefix('"').and_then(|s| s.strip_suffix('"')) {
+        Value::from(unquoted)
+    } else if let Ok(i) = s.parse::<i64>() {
+        Value::from(i)
+    } else if let Ok(f) = s.parse::<f64>() {
+        Value::from(f)
+    } else if s == "true" {
+        Value::from(true)
+    } else if s == "false" {
+        Value::from(false)
+    } else {
+        Value::from(s)
+    }
+}
+
+fn render_shortcode(
+    env: &Environment<'_>,
+    name: &str,
+    mut args: HashMap<String, Value>,
+    nth: usize,
+    body: Option<&str>,
+) -> anyhow::Result<String> {
+    let template_name = format!("shortcodes/{name}.html");
+    let template = env
+        .get_template(&template_name)
+        .with_context(|| format!("no shortcode template `{template_name}` found"))?;
+
+    args.insert("nth".to_owned(), Value::from(nth));
+    if let Some(body) = body {
+        args.insert("body".to_owned(), Value::from(body));
+    }
+
+    template.render(args).with_context(|| format!("rendering shortcode `{name}`"))
+}
@@ -4,15 +4,20 @@ use std::{
 };
 
 use anyhow::Context as _;
+use camino::{Utf8Path, Utf8PathBuf};
+use fs_err as fs;
 use pulldown_cmark::{CodeBlockKind, CowStr, Event, Tag, TagEnd};
 use syntect::{
     highlighting::{Theme, ThemeSet},
-    html::highlighted_html_for_string,
-    parsing::SyntaxSet,
+    html::{ClassStyle, ClassedHTMLGenerator, css_for_theme_with_class_style, highlighted_html_for_string},
+    parsing::{SyntaxReference, SyntaxSet},
+    util::LinesWithEndings,
 };
 use tracing::{error, warn};
 
-pub(crate) type LazySyntaxHighlighter = Arc<OnceLock<Option<SyntaxHighlighter>>>;
+use crate::config::SyntaxHighlightStyle;
+
+pub(crate) type LazySyntaxHighlighter = Arc<OnceLock<SyntaxHighlighter>>;
 
 pub(crate) struct SyntaxHighlighter {
     syntaxset: SyntaxSet,
@@ -20,12 +25,14 @@ pub(crate) struct SyntaxHighlighter {
 }
 
 impl SyntaxHighlighter {
-    pub(crate) fn new() -> anyhow::Result<SyntaxHighlighter> {
+    /// Builds the syntax set and theme set once, from every `.sublime-syntax`
+    /// and theme file found (recursively) in `sublime_dir`.
+    pub(crate) fn new(sublime_dir: &Utf8Path) -> anyhow::Result<SyntaxHighlighter> {
         let mut syntaxset_builder = SyntaxSet::load_defaults_newlines().into_builder();
-        syntaxset_builder.add_from_folder("theme/sublime", true)?;
+        syntaxset_builder.add_from_folder(sublime_dir, true)?;
         let syntaxset = syntaxset_builder.build();
 
-        let themes = ThemeSet::load_from_folder("theme/sublime")
+        let themes = ThemeSet::load_from_folder(sublime_dir)
             .context("Loading syntax highlighting themes")?
             .themes;
 
@@ -45,6 +52,7 @@ impl SyntaxHighlighter {
         &'a self,
         events: impl Iterator<Item = Event<'a>>,
         theme_name: &str,
+        style: SyntaxHighlightStyle,
     ) -> anyhow::Result<impl Iterator<Item = Event<'a>>> {
         let theme = self
             .themes
@@ -75,21 +83,23 @@ impl SyntaxHighlighter {
                         });
 
                     let code = &current_code_block_contents;
-                    let highlight_result =
-                        highlighted_html_for_string(code, &self.syntaxset, syntax, theme);
-
-                    let event = match highlight_result {
-                        Ok(html) => Event::Html(CowStr::from(html)),
-                        Err(e) => {
-                            error!("Failed to highlight code block: {e}");
+                    let html = match style {
+                        SyntaxHighlightStyle::Inline => {
+                            highlighted_html_for_string(code, &self.syntaxset, syntax, theme)
+                                .unwrap_or_else(|e| {
+                                    error!("Failed to highlight code block: {e}");
 
-                            // FIXME: Use flat_map with three events here instead
-                            Event::Html(CowStr::from(format!("<code>{code}</code>")))
+                                    // FIXME: Use flat_map with three events here instead
+                                    format!("<code>{code}</code>")
+                                })
+                        }
+                        SyntaxHighlightStyle::Classed => {
+                            highlight_classed(code, syntax, &self.syntaxset, &language)
                         }
                     };
 
                     current_code_block_contents.clear();
-                    Some(event)
+                    Some(Event::Html(CowStr::from(html)))
                 }
                 None => Some(ev),
             },
@@ -104,4 +114,60 @@ impl SyntaxHighlighter {
             ev => Some(ev),
         }))
     }
+
+    /// Writes a companion stylesheet for every loaded theme (e.g.
+    /// `syntax-theme-<name>.css`), for use with code blocks rendered in
+    /// [`SyntaxHighlightStyle::Classed`] mode. Returns the paths written, so
+    /// callers can register them with `OutputDirManager`.
+    pub(crate) fn write_stylesheets(&self, output_dir: &Utf8Path) -> anyhow::Result<Vec<Utf8PathBuf>> {
+        let mut paths = Vec::new();
+        for name in self.themes.keys() {
+            let css = self.css_for_theme(name)?;
+            let path = output_dir.join(format!("syntax-theme-{}.css", slugify(name)));
+            fs::write(&path, css).with_context(|| format!("writing `{path}`"))?;
+            paths.push(path);
+        }
+
+        Ok(paths)
+    }
+
+    /// Generates a standalone stylesheet for a single theme, for the
+    /// `syntect-to-css` CLI subcommand. Export a light and a dark theme this
+    /// way and wrap each `<link>`/`@import` in a `prefers-color-scheme`
+    /// media query to let the color scheme switch without re-rendering
+    /// content.
+    pub(crate) fn css_for_theme(&self, theme_name: &str) -> anyhow::Result<String> {
+        let theme = self
+            .themes
+            .get(theme_name)
+            .with_context(|| format!("theme `{theme_name}` not found"))?;
+        css_for_theme_with_class_style(theme, ClassStyle::Spaced)
+            .with_context(|| format!("generating stylesheet for theme `{theme_name}`"))
+    }
+}
+
+/// Renders a code block as `<span class="...">` tokens referencing scope
+/// classes from a shared stylesheet, instead of inlining per-token styles.
+fn highlight_classed(code: &str, syntax: &SyntaxReference, syntaxset: &SyntaxSet, language: &str) -> String {
+    let mut generator =
+        ClassedHTMLGenerator::new_with_class_style(syntax, syntaxset, ClassStyle::Spaced);
+    for line in LinesWithEndings::from(code) {
+        // Infallible for the line-based API; syntect only errors on
+        // line-less-than-full-file parsing misuse, which doesn't apply here.
+        let _ = generator.parse_html_for_line_which_includes_newline(line);
+    }
+    let highlighted = generator.finalize();
+
+    format!(r#"<pre><code class="language-{}">{highlighted}</code></pre>"#, escape_html_attr(language))
+}
+
+fn escape_html_attr(s: &str) -> String {
+    s.replace('&', "&amp;").replace('"', "&quot;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+/// Turns a theme name into a filesystem- and URL-safe slug.
+fn slugify(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '-' })
+        .collect()
 }
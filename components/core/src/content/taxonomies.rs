@@ -0,0 +1,91 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use super::{DirectoryMetadata, FileMetadata};
+
+/// Collects every page's taxonomy terms into `taxonomy name -> term -> pages`.
+///
+/// A page can declare terms for a configured taxonomy either via the
+/// dedicated `taxonomies` frontmatter table, or, for users who'd rather not
+/// repeat the taxonomy name, via a same-named `extra` array, e.g.
+/// `extra.tags = ["rust", "ssg"]` instead of `taxonomies.tags = [...]`.
+pub(super) fn collect(
+    root: &DirectoryMetadata,
+    taxonomy_names: &[String],
+) -> BTreeMap<String, BTreeMap<String, Vec<FileMetadata>>> {
+    let mut by_taxonomy = BTreeMap::new();
+    collect_dir(root, taxonomy_names, &mut by_taxonomy);
+    by_taxonomy
+}
+
+fn collect_dir(
+    dir: &DirectoryMetadata,
+    taxonomy_names: &[String],
+    by_taxonomy: &mut BTreeMap<String, BTreeMap<String, Vec<FileMetadata>>>,
+) {
+    if let Some(files) = dir.files.get() {
+        for file in files {
+            for taxonomy in taxonomy_names {
+                for term in terms_for(file, taxonomy) {
+                    by_taxonomy
+                        .entry(taxonomy.clone())
+                        .or_default()
+                        .entry(term)
+                        .or_insert_with(Vec::new)
+                        .push(file.clone());
+                }
+            }
+        }
+    }
+
+    for subdir in dir.subdirs.values() {
+        collect_dir(subdir, taxonomy_names, by_taxonomy);
+    }
+}
+
+/// Returns `file`'s terms for `taxonomy`, preferring the dedicated
+/// `taxonomies` table and falling back to an `extra` array of the same name.
+fn terms_for(file: &FileMetadata, taxonomy: &str) -> Vec<String> {
+    if let Some(terms) = file.taxonomies.get(taxonomy) {
+        return terms.clone();
+    }
+
+    match file.extra.get(taxonomy) {
+        Some(toml::Value::Array(terms)) => {
+            terms.iter().filter_map(|term| Some(term.as_str()?.to_owned())).collect()
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// Turns a taxonomy term into a URL-safe slug: lowercased, with runs of
+/// non-alphanumeric characters collapsed to a single `-`.
+pub(super) fn slugify(term: &str) -> String {
+    let mut slug = String::with_capacity(term.len());
+    let mut last_was_dash = true; // avoid a leading dash
+
+    for c in term.chars() {
+        if c.is_alphanumeric() {
+            slug.extend(c.to_lowercase());
+            last_was_dash = false;
+        } else if !last_was_dash {
+            slug.push('-');
+            last_was_dash = true;
+        }
+    }
+
+    if slug.ends_with('-') {
+        slug.pop();
+    }
+
+    slug
+}
+
+/// One entry of a taxonomy's term listing, as exposed to `taxonomy_list.html`.
+#[derive(Serialize)]
+pub(super) struct TermListEntry {
+    pub term: String,
+    pub slug: String,
+    pub count: usize,
+}
@@ -0,0 +1,134 @@
+use std::collections::HashMap;
+
+use pulldown_cmark::{html::push_html, CowStr, Event, HeadingLevel, Tag, TagEnd};
+use serde::Serialize;
+
+/// A single heading in a page's table of contents, with its nested
+/// subheadings.
+#[derive(Clone, Debug, Serialize)]
+pub(crate) struct TocEntry {
+    pub level: u8,
+    pub title: String,
+    pub id: String,
+    pub children: Vec<TocEntry>,
+}
+
+/// Rewrites heading events to carry a unique `id` anchor (for fragment
+/// links) and collects them into a nested table of contents.
+///
+/// Runs eagerly rather than lazily like the other event adapters in this
+/// module, since the returned tree must be fully built before the rewritten
+/// events are handed off to `push_html`.
+pub(crate) fn extract_toc<'a>(
+    events: impl Iterator<Item = Event<'a>>,
+) -> (Vec<Event<'a>>, Vec<TocEntry>) {
+    let mut seen_slugs = HashMap::new();
+    let mut out_events = Vec::new();
+    let mut roots = Vec::new();
+    // Ancestor chain of the heading currently being built, shallowest first.
+    let mut open: Vec<TocEntry> = Vec::new();
+
+    let mut current_level = None;
+    let mut current_title = String::new();
+    let mut current_inner_events = Vec::new();
+
+    for event in events {
+        if let Event::Start(Tag::Heading { level, .. }) = event {
+            current_level = Some(level);
+            current_title.clear();
+            current_inner_events.clear();
+            continue;
+        }
+
+        let Some(level) = current_level else {
+            out_events.push(event);
+            continue;
+        };
+
+        if let Event::End(TagEnd::Heading(_)) = event {
+            current_level = None;
+
+            let id = unique_slug(&mut seen_slugs, &current_title);
+            let mut inner_html = String::new();
+            push_html(&mut inner_html, current_inner_events.drain(..));
+
+            let n = heading_level_number(level);
+            out_events.push(Event::Html(CowStr::from(format!(
+                r#"<h{n} id="{id}">{inner_html}</h{n}>"#
+            ))));
+
+            close_until(&mut open, &mut roots, n);
+            open.push(TocEntry { level: n, title: current_title.clone(), id, children: Vec::new() });
+            continue;
+        }
+
+        if let Event::Text(text) | Event::Code(text) = &event {
+            current_title.push_str(text);
+        }
+        current_inner_events.push(event);
+    }
+
+    while let Some(node) = open.pop() {
+        attach(&mut open, &mut roots, node);
+    }
+
+    (out_events, roots)
+}
+
+/// Closes out ancestors at `level` or deeper, attaching each one to its
+/// parent (or the root list, once there's no shallower ancestor left open).
+fn close_until(open: &mut Vec<TocEntry>, roots: &mut Vec<TocEntry>, level: u8) {
+    while matches!(open.last(), Some(top) if top.level >= level) {
+        let node = open.pop().unwrap();
+        attach(open, roots, node);
+    }
+}
+
+fn attach(open: &mut [TocEntry], roots: &mut Vec<TocEntry>, node: TocEntry) {
+    match open.last_mut() {
+        Some(parent) => parent.children.push(node),
+        None => roots.push(node),
+    }
+}
+
+fn heading_level_number(level: HeadingLevel) -> u8 {
+    match level {
+        HeadingLevel::H1 => 1,
+        HeadingLevel::H2 => 2,
+        HeadingLevel::H3 => 3,
+        HeadingLevel::H4 => 4,
+        HeadingLevel::H5 => 5,
+        HeadingLevel::H6 => 6,
+    }
+}
+
+/// Turns heading text into a URL-safe slug, deduping collisions with a
+/// `-1`, `-2`, ... suffix.
+fn unique_slug(seen: &mut HashMap<String, u32>, text: &str) -> String {
+    let base = slugify(text);
+    let count = seen.entry(base.clone()).or_insert(0);
+    let id = if *count == 0 { base } else { format!("{base}-{count}") };
+    *count += 1;
+    id
+}
+
+fn slugify(text: &str) -> String {
+    let mut slug = String::with_capacity(text.len());
+    let mut last_was_dash = true; // avoid a leading dash
+
+    for c in text.chars() {
+        if c.is_alphanumeric() {
+            slug.extend(c.to_lowercase());
+            last_was_dash = false;
+        } else if !last_was_dash {
+            slug.push('-');
+            last_was_dash = true;
+        }
+    }
+
+    if slug.ends_with('-') {
+        slug.pop();
+    }
+
+    slug
+}
@@ -3,13 +3,14 @@ use std::io::{BufRead, ErrorKind, Seek};
 use anyhow::Context as _;
 use serde::de::DeserializeOwned;
 
-/// Looks for TOML frontmatter in the given reader and parses it if found.
+/// Looks for TOML (`+++`), YAML (`---`), or JSON (`;;;` or a leading `{`)
+/// frontmatter in the given reader and parses it if found.
 ///
-/// If the input does not start with a frontmatter delimiter (line of `+++` with
-/// optional trailing whitespace), returns `Ok(None)`. If the frontmatter
-/// delimiter is found, parses all the lines between that one and the next one
-/// found. If successful, the input will be advanced such that the remaining
-/// content after the frontmatter can be processed from it.
+/// If the input does not start with a frontmatter delimiter, returns the
+/// default value. If a delimiter is found, parses all the lines between that
+/// one and the matching closing delimiter. If successful, the input will be
+/// advanced such that the remaining content after the frontmatter can be
+/// processed from it.
 pub(crate) fn parse_frontmatter<T>(input: impl BufRead + Seek) -> anyhow::Result<T>
 where
     T: Default + DeserializeOwned,
@@ -35,21 +36,78 @@ where
         }
     }
 
-    if buf.trim_end() != "+++" {
-        bail_default!();
-    }
+    // `{`-delimited JSON frontmatter is self-delimiting (no closing fence
+    // line), so it's handled separately from the `+++`/`---`/`;;;` formats,
+    // which all share the same "read until a matching delimiter line" shape.
+    let delimiter = match buf.trim_end() {
+        "+++" => "+++",
+        "---" => "---",
+        ";;;" => ";;;",
+        "{" => return parse_json_brace_frontmatter(limited.into_inner(), buf),
+        _ => bail_default!(),
+    };
 
     // If frontmatter delimiter was found, don't limit reading anymore.
     let mut input = limited.into_inner();
     buf.clear();
     loop {
         input.read_line(&mut buf)?;
-        if buf.lines().next_back().is_some_and(|l| l.trim_end() == "+++") {
-            let frontmatter_end_idx = buf.rfind("+++").expect("already found once");
+        if buf.lines().next_back().is_some_and(|l| l.trim_end() == delimiter) {
+            let frontmatter_end_idx = buf.rfind(delimiter).expect("already found once");
             buf.truncate(frontmatter_end_idx);
             break;
         }
     }
 
-    toml::from_str(&buf).context("parsing frontmatter")
+    match delimiter {
+        "+++" => toml::from_str(&buf).context("parsing TOML frontmatter"),
+        "---" => parse_yaml_frontmatter(&buf),
+        ";;;" => parse_json_frontmatter(&buf),
+        _ => unreachable!(),
+    }
+}
+
+/// Reads lines starting from the opening `{` (already in `buf`) through to
+/// the line that closes it, then parses the result as JSON.
+fn parse_json_brace_frontmatter<T: DeserializeOwned>(
+    mut input: impl BufRead,
+    mut buf: String,
+) -> anyhow::Result<T> {
+    loop {
+        let mut line = String::new();
+        input.read_line(&mut line)?;
+        let is_end = line.trim_end() == "}";
+        buf.push_str(&line);
+        if is_end {
+            break;
+        }
+    }
+
+    parse_json_frontmatter(&buf)
+}
+
+#[cfg(feature = "yaml")]
+fn parse_yaml_frontmatter<T: DeserializeOwned>(buf: &str) -> anyhow::Result<T> {
+    serde_yaml::from_str(buf).context("parsing YAML frontmatter")
+}
+
+#[cfg(not(feature = "yaml"))]
+fn parse_yaml_frontmatter<T: DeserializeOwned>(_buf: &str) -> anyhow::Result<T> {
+    anyhow::bail!(
+        "hinoki was compiled without support for YAML frontmatter.\
+         Please recompile with the 'yaml' feature enabled."
+    )
+}
+
+#[cfg(feature = "json")]
+fn parse_json_frontmatter<T: DeserializeOwned>(buf: &str) -> anyhow::Result<T> {
+    serde_json::from_str(buf).context("parsing JSON frontmatter")
+}
+
+#[cfg(not(feature = "json"))]
+fn parse_json_frontmatter<T: DeserializeOwned>(_buf: &str) -> anyhow::Result<T> {
+    anyhow::bail!(
+        "hinoki was compiled without support for JSON frontmatter.\
+         Please recompile with the 'json' feature enabled."
+    )
 }
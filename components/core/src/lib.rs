@@ -5,6 +5,7 @@ use camino::Utf8Path;
 use fs_err as fs;
 use tracing::warn;
 
+mod assets;
 pub mod build;
 pub mod config;
 mod content;
@@ -18,7 +19,12 @@ pub use self::config::Config;
 pub fn read_config(path: &Utf8Path) -> anyhow::Result<Config> {
     let mut config = match fs::read_to_string(path) {
         Ok(config_str) => {
-            toml::from_str(&config_str).with_context(|| format!("Failed to parse `{path}`"))?
+            let (table, include_files) = config::read_merged_config_table(path, &config_str)?;
+            let mut config: Config = toml::Value::Table(table)
+                .try_into()
+                .with_context(|| format!("Failed to parse `{path}`"))?;
+            config.include_files = include_files;
+            config
         }
         Err(e) if e.kind() == io::ErrorKind::NotFound && path == "config.toml" => {
             warn!("`{path}` not found, falling back to defaults");
@@ -32,3 +38,12 @@ pub fn read_config(path: &Utf8Path) -> anyhow::Result<Config> {
     config.path = path.to_owned();
     Ok(config)
 }
+
+/// Renders a loaded syntax highlighting theme as a standalone CSS
+/// stylesheet, for the `syntect-to-css` CLI subcommand. Lets a theme be
+/// previewed or shared as one file, independent of `[syntax_highlight]
+/// style` or running a full build.
+#[cfg(feature = "syntax-highlighting")]
+pub fn syntect_to_css(config: &Config, theme: &str) -> anyhow::Result<String> {
+    content::SyntaxHighlighter::new(&config.sublime_dir())?.css_for_theme(theme)
+}
@@ -1,8 +1,8 @@
-use std::sync::mpsc;
+use std::{collections::HashMap, sync::mpsc};
 
 use anyhow::{format_err, Context as _};
 use bumpalo_herd::Herd;
-use camino::Utf8Path;
+use camino::{Utf8Path, Utf8PathBuf};
 use fs_err::{self as fs};
 use minijinja::UndefinedBehavior;
 use rayon::iter::{ParallelBridge as _, ParallelIterator as _};
@@ -12,11 +12,17 @@ use walkdir::WalkDir;
 pub(crate) mod context;
 pub(crate) mod filters;
 pub(crate) mod functions;
+#[cfg(feature = "images")]
+mod images;
 
+/// Loads every template under `template_dir` into a fresh
+/// [`minijinja::Environment`], alongside a content hash of each template's
+/// raw source keyed by its path relative to `template_dir`, for the
+/// incremental build cache to compare against.
 pub(crate) fn load_templates<'a>(
     template_dir: &Utf8Path,
     alloc: &'a Herd,
-) -> anyhow::Result<minijinja::Environment<'a>> {
+) -> anyhow::Result<(minijinja::Environment<'a>, HashMap<Utf8PathBuf, String>)> {
     struct TemplateSource<'b> {
         /// Path relative to the template directory
         rel_path: &'b str,
@@ -72,11 +78,13 @@ pub(crate) fn load_templates<'a>(
 
     let template_env_ref = &mut template_env;
     let add_templates = move || {
+        let mut template_hashes = HashMap::new();
         while let Ok(TemplateSource { rel_path, source }) = template_source_rx.recv() {
+            template_hashes.insert(Utf8PathBuf::from(rel_path), crate::util::content_hash(source));
             template_env_ref.add_template(rel_path, source)?;
         }
 
-        anyhow::Ok(())
+        anyhow::Ok(template_hashes)
     };
 
     let (read_templates_result, add_templates_result) = rayon::join(read_templates, add_templates);
@@ -84,10 +92,10 @@ pub(crate) fn load_templates<'a>(
     // Prioritize errors from add_templates, if it fails then read_templates
     // almost definitely also fails with a RecvError and the case of a
     // parallel I/O error is super rare and not very important.
-    add_templates_result?;
+    let template_hashes = add_templates_result?;
     read_templates_result?;
 
-    Ok(template_env)
+    Ok((template_env, template_hashes))
 }
 
 fn environment<'a>() -> minijinja::Environment<'a> {
@@ -97,7 +105,14 @@ fn environment<'a>() -> minijinja::Environment<'a> {
     env.add_filter("markdown", filters::markdown);
     env.add_function("get_file", functions::get_file);
     env.add_function("get_files", functions::get_files);
+    env.add_function("sorted_by", functions::sorted_by);
+    env.add_function("get_taxonomy", functions::get_taxonomy);
+    env.add_function("get_taxonomy_term", functions::get_taxonomy_term);
+    env.add_function("get_hash", functions::get_hash);
     env.add_function("load_data", functions::load_data);
+    env.add_function("load_data_glob", functions::load_data_glob);
+    #[cfg(feature = "images")]
+    env.add_function("resize_image", functions::resize_image);
 
     #[cfg(feature = "datetime")]
     {
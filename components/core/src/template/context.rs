@@ -1,39 +1,214 @@
 use std::{
-    collections::BTreeMap,
+    collections::{BTreeMap, HashMap},
     fmt,
-    sync::{Arc, OnceLock},
+    sync::{
+        Arc, Mutex, OnceLock,
+        atomic::{AtomicBool, Ordering},
+    },
     time::Duration,
 };
 
+use anyhow::Context as _;
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use camino::{Utf8Path, Utf8PathBuf};
+use fs_err as fs;
 use serde::{
     de::{self, IntoDeserializer as _},
     Deserialize, Serialize, Serializer,
 };
+use sha2::{Digest, Sha256};
 use tracing::warn;
 
 #[cfg(feature = "syntax-highlighting")]
 use crate::content::{LazySyntaxHighlighter, SyntaxHighlighter};
+#[cfg(feature = "markdown")]
+use crate::config::MarkdownConfig;
+#[cfg(feature = "syntax-highlighting")]
+use crate::config::SyntaxHighlightStyle;
+#[cfg(feature = "markdown")]
+use crate::content::TocEntry;
 use crate::{
     content::{DirectoryMetadata, FileMetadata},
     util::OrderBiMap,
 };
 
+#[cfg(feature = "images")]
+use super::images::{ImageCache, ImageCacheKey};
+
 #[derive(Clone)]
 pub(crate) struct GlobalContext {
     #[cfg(feature = "syntax-highlighting")]
     syntax_highlighter: LazySyntaxHighlighter,
+    #[cfg(feature = "syntax-highlighting")]
+    sublime_dir: Utf8PathBuf,
+    #[cfg(feature = "syntax-highlighting")]
+    syntax_highlight_style: SyntaxHighlightStyle,
+    #[cfg(feature = "markdown")]
+    markdown_config: MarkdownConfig,
+    #[cfg(feature = "markdown")]
+    base_url: Option<String>,
+    /// Aggregated taxonomy terms, populated once the whole content directory
+    /// has been walked. Shared by every page's [`HinokiContext`] so
+    /// `get_taxonomy` can be called from any template.
+    taxonomies: Arc<OnceLock<BTreeMap<String, BTreeMap<String, Vec<FileMetadata>>>>>,
+    /// Maps a content directory (relative to the content dir) to the source
+    /// paths of pages recorded as depending on its file listing, via
+    /// `get_file`/`get_files`. Owned by [`Build`][crate::build::Build], which
+    /// uses it to restrict a rebuild to the pages affected by a change.
+    dependents: Arc<Mutex<HashMap<Utf8PathBuf, Vec<Utf8PathBuf>>>>,
+    /// Maps a canonicalized data file path to the source paths of pages
+    /// recorded as depending on it via `load_data`. Owned by
+    /// [`Build`][crate::build::Build], same as `dependents`.
+    data_dependents: Arc<Mutex<HashMap<Utf8PathBuf, Vec<Utf8PathBuf>>>>,
+    asset_dir: Utf8PathBuf,
+    content_dir: Utf8PathBuf,
+    /// Caches the base64-encoded SHA-256 hash of files looked up via
+    /// `get_hash`, keyed by the path as passed to that function, so a file
+    /// referenced from several templates is only read and hashed once.
+    hash_cache: Arc<Mutex<HashMap<Utf8PathBuf, Arc<str>>>>,
+    #[cfg(feature = "images")]
+    output_dir: Utf8PathBuf,
+    /// Caches images resized and re-encoded via `resize_image`, keyed by
+    /// source path plus transform parameters.
+    #[cfg(feature = "images")]
+    image_cache: Arc<ImageCache>,
 }
 
 impl GlobalContext {
     pub(crate) fn new(
         #[cfg(feature = "syntax-highlighting")] syntax_highlighter: LazySyntaxHighlighter,
+        #[cfg(feature = "syntax-highlighting")] sublime_dir: Utf8PathBuf,
+        #[cfg(feature = "syntax-highlighting")] syntax_highlight_style: SyntaxHighlightStyle,
+        #[cfg(feature = "markdown")] markdown_config: MarkdownConfig,
+        #[cfg(feature = "markdown")] base_url: Option<String>,
+        dependents: Arc<Mutex<HashMap<Utf8PathBuf, Vec<Utf8PathBuf>>>>,
+        data_dependents: Arc<Mutex<HashMap<Utf8PathBuf, Vec<Utf8PathBuf>>>>,
+        asset_dir: Utf8PathBuf,
+        content_dir: Utf8PathBuf,
+        #[cfg(feature = "images")] output_dir: Utf8PathBuf,
     ) -> Self {
-        Self { syntax_highlighter }
+        Self {
+            #[cfg(feature = "syntax-highlighting")]
+            syntax_highlighter,
+            #[cfg(feature = "syntax-highlighting")]
+            sublime_dir,
+            #[cfg(feature = "syntax-highlighting")]
+            syntax_highlight_style,
+            #[cfg(feature = "markdown")]
+            markdown_config,
+            #[cfg(feature = "markdown")]
+            base_url,
+            taxonomies: Arc::new(OnceLock::new()),
+            dependents,
+            data_dependents,
+            asset_dir,
+            content_dir,
+            hash_cache: Arc::new(Mutex::new(HashMap::new())),
+            #[cfg(feature = "images")]
+            output_dir,
+            #[cfg(feature = "images")]
+            image_cache: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    fn record_dependency(&self, dir: Utf8PathBuf, dependent: Utf8PathBuf) {
+        self.dependents.lock().unwrap().entry(dir).or_default().push(dependent);
+    }
+
+    fn record_data_dependency(&self, data_path: Utf8PathBuf, dependent: Utf8PathBuf) {
+        self.data_dependents.lock().unwrap().entry(data_path).or_default().push(dependent);
+    }
+
+    /// Returns the base64-encoded SHA-256 hash of the file at `rel_path`,
+    /// resolved against the asset directory first and the content directory
+    /// second. Cached by `rel_path` so repeated lookups of the same file
+    /// across templates don't re-read and re-hash it.
+    pub(crate) fn file_hash(&self, rel_path: &Utf8Path) -> anyhow::Result<Arc<str>> {
+        if let Some(hash) = self.hash_cache.lock().unwrap().get(rel_path) {
+            return Ok(hash.clone());
+        }
+
+        let full_path = [&self.asset_dir, &self.content_dir]
+            .into_iter()
+            .map(|dir| dir.join(rel_path))
+            .find(|path| path.is_file())
+            .with_context(|| format!("no such file `{rel_path}`"))?;
+
+        let contents = fs::read(&full_path)?;
+        let hash: Arc<str> = STANDARD.encode(Sha256::digest(&contents)).into();
+
+        self.hash_cache.lock().unwrap().insert(rel_path.to_owned(), hash.clone());
+        Ok(hash)
+    }
+
+    /// Resizes (cropping to fill) the image at `rel_path`, resolved the same
+    /// way as [`Self::file_hash`], to `width`x`height`, re-encodes it as
+    /// `format` at `quality`, and returns the public URL of the generated
+    /// asset. Identical transforms requested concurrently from multiple
+    /// pages are only computed once.
+    #[cfg(feature = "images")]
+    pub(crate) fn resize_image(
+        &self,
+        rel_path: &Utf8Path,
+        width: u32,
+        height: u32,
+        format: &str,
+        quality: u8,
+    ) -> anyhow::Result<String> {
+        let source_path = [&self.asset_dir, &self.content_dir]
+            .into_iter()
+            .map(|dir| dir.join(rel_path))
+            .find(|path| path.is_file())
+            .with_context(|| format!("no such image `{rel_path}`"))?;
+
+        let key = ImageCacheKey {
+            path: rel_path.to_owned(),
+            width,
+            height,
+            format: format.to_owned(),
+            quality,
+        };
+        super::images::resize(&self.image_cache, key, &source_path, &self.output_dir)
+    }
+
+    pub(crate) fn set_taxonomies(
+        &self,
+        data: BTreeMap<String, BTreeMap<String, Vec<FileMetadata>>>,
+    ) {
+        self.taxonomies.set(data).expect("must only be called once");
+    }
+
+    pub(crate) fn taxonomies(&self) -> &BTreeMap<String, BTreeMap<String, Vec<FileMetadata>>> {
+        loop {
+            if let Some(initialized) = self.taxonomies.get() {
+                return initialized;
+            }
+
+            if rayon::yield_now().unwrap() == rayon::Yield::Idle {
+                warn!("No available work");
+                std::thread::sleep(Duration::from_millis(10));
+            }
+        }
     }
 
     #[cfg(feature = "syntax-highlighting")]
     pub(crate) fn syntax_highlighter(&self) -> anyhow::Result<&SyntaxHighlighter> {
-        self.syntax_highlighter.get_or_try_init(SyntaxHighlighter::new)
+        self.syntax_highlighter.get_or_try_init(|| SyntaxHighlighter::new(&self.sublime_dir))
+    }
+
+    #[cfg(feature = "syntax-highlighting")]
+    pub(crate) fn syntax_highlight_style(&self) -> SyntaxHighlightStyle {
+        self.syntax_highlight_style
+    }
+
+    #[cfg(feature = "markdown")]
+    pub(crate) fn markdown_config(&self) -> &MarkdownConfig {
+        &self.markdown_config
+    }
+
+    #[cfg(feature = "markdown")]
+    pub(crate) fn base_url(&self) -> Option<&str> {
+        self.base_url.as_deref()
     }
 }
 
@@ -42,6 +217,9 @@ pub(crate) struct DirectoryContext {
     subdirs: Arc<BTreeMap<String, DirectoryMetadata>>,
     files: Arc<OnceLock<Vec<FileMetadata>>>,
     file_indices_by_date: Arc<OnceLock<OrderBiMap>>,
+    file_indices_by_title: Arc<OnceLock<OrderBiMap>>,
+    file_indices_by_slug: Arc<OnceLock<OrderBiMap>>,
+    file_indices_by_weight: Arc<OnceLock<OrderBiMap>>,
 }
 
 impl DirectoryContext {
@@ -50,6 +228,9 @@ impl DirectoryContext {
             subdirs,
             files: Arc::new(OnceLock::new()),
             file_indices_by_date: Arc::new(OnceLock::new()),
+            file_indices_by_title: Arc::new(OnceLock::new()),
+            file_indices_by_slug: Arc::new(OnceLock::new()),
+            file_indices_by_weight: Arc::new(OnceLock::new()),
         }
     }
 
@@ -64,16 +245,32 @@ impl DirectoryContext {
 
 pub(crate) struct RenderContext {
     pub current_file_idx: Option<usize>,
+    /// Path of the page currently being rendered, relative to the content
+    /// dir. Used to record which directories it reads from via
+    /// `get_file`/`get_files`.
+    pub source_path: Arc<Utf8Path>,
     #[cfg(feature = "syntax-highlighting")]
     pub syntax_highlight_theme: Option<String>,
+    /// Set by [`HinokiContext::mark_aggregate_use`] once this page's render
+    /// calls a function that reads data beyond its own content file, so the
+    /// incremental build cache knows not to skip-render it on a later build
+    /// based on matching hashes alone (see
+    /// `crate::build::cache::CacheEntry::is_aggregate`).
+    used_aggregate: AtomicBool,
 }
 
 impl RenderContext {
     pub(crate) fn new(
         current_file_idx: Option<usize>,
+        source_path: Arc<Utf8Path>,
         #[cfg(feature = "syntax-highlighting")] syntax_highlight_theme: Option<String>,
     ) -> Self {
-        Self { syntax_highlight_theme, current_file_idx }
+        Self {
+            syntax_highlight_theme,
+            current_file_idx,
+            source_path,
+            used_aggregate: AtomicBool::new(false),
+        }
     }
 }
 
@@ -102,10 +299,76 @@ impl HinokiContext {
         self.render.syntax_highlight_theme.as_deref()
     }
 
+    #[cfg(feature = "syntax-highlighting")]
+    pub(crate) fn syntax_highlight_style(&self) -> SyntaxHighlightStyle {
+        self.global.syntax_highlight_style()
+    }
+
+    #[cfg(feature = "markdown")]
+    pub(crate) fn markdown_config(&self) -> &MarkdownConfig {
+        self.global.markdown_config()
+    }
+
+    #[cfg(feature = "markdown")]
+    pub(crate) fn base_url(&self) -> Option<&str> {
+        self.global.base_url()
+    }
+
     pub(super) fn get_subdir(&self, subdir_name: &str) -> Option<&DirectoryMetadata> {
         self.directory.subdirs.get(subdir_name)
     }
 
+    pub(super) fn taxonomy(&self, name: &str) -> Option<&BTreeMap<String, Vec<FileMetadata>>> {
+        self.mark_aggregate_use();
+        self.global.taxonomies().get(name)
+    }
+
+    pub(super) fn source_path(&self) -> &Utf8Path {
+        &self.render.source_path
+    }
+
+    pub(super) fn record_dependency(&self, dir: Utf8PathBuf) {
+        self.mark_aggregate_use();
+        self.global.record_dependency(dir, self.render.source_path.to_path_buf());
+    }
+
+    pub(super) fn record_data_dependency(&self, data_path: Utf8PathBuf) {
+        self.mark_aggregate_use();
+        self.global.record_data_dependency(data_path, self.render.source_path.to_path_buf());
+    }
+
+    /// Flags the page currently being rendered as having read data beyond
+    /// its own content file, via `get_file`/`get_files`, `load_data`, or
+    /// `get_taxonomy`/`get_taxonomy_term`. Checked by
+    /// [`Self::used_aggregate`] after the render completes.
+    fn mark_aggregate_use(&self) {
+        self.render.used_aggregate.store(true, Ordering::Relaxed);
+    }
+
+    /// Whether the page currently being rendered called a function flagged
+    /// by [`Self::mark_aggregate_use`] during this render. Consulted by
+    /// `crate::content::render_file` once rendering completes, to decide
+    /// whether to flag this page's incremental cache entry aggregate.
+    pub(crate) fn used_aggregate(&self) -> bool {
+        self.render.used_aggregate.load(Ordering::Relaxed)
+    }
+
+    pub(super) fn file_hash(&self, rel_path: &Utf8Path) -> anyhow::Result<Arc<str>> {
+        self.global.file_hash(rel_path)
+    }
+
+    #[cfg(feature = "images")]
+    pub(super) fn resize_image(
+        &self,
+        rel_path: &Utf8Path,
+        width: u32,
+        height: u32,
+        format: &str,
+        quality: u8,
+    ) -> anyhow::Result<String> {
+        self.global.resize_image(rel_path, width, height, format, quality)
+    }
+
     pub(super) fn current_dir_files(&self) -> &[FileMetadata] {
         loop {
             if let Some(initialized) = self.directory.files.get() {
@@ -125,14 +388,26 @@ impl HinokiContext {
 
     pub(super) fn get_or_init_file_indices_by(
         &self,
-        ordering: Ordering,
+        key: OrderKey,
         current_dir_files: &[FileMetadata],
     ) -> &OrderBiMap {
-        match ordering {
-            Ordering::Date => self
+        match key {
+            OrderKey::Date => self
                 .directory
                 .file_indices_by_date
                 .get_or_init(|| OrderBiMap::new(current_dir_files, |file| file.date)),
+            OrderKey::Title => self
+                .directory
+                .file_indices_by_title
+                .get_or_init(|| OrderBiMap::new(current_dir_files, |file| file.title.clone())),
+            OrderKey::Slug => self
+                .directory
+                .file_indices_by_slug
+                .get_or_init(|| OrderBiMap::new(current_dir_files, |file| file.slug.clone())),
+            OrderKey::Weight => self
+                .directory
+                .file_indices_by_weight
+                .get_or_init(|| OrderBiMap::new(current_dir_files, |file| file.weight)),
         }
     }
 }
@@ -152,12 +427,22 @@ impl minijinja::value::Object for HinokiContext {
 #[derive(Serialize)]
 pub(crate) struct TemplateContext<'a> {
     pub content: String,
-    pub page: &'a FileMetadata,
+    pub page: PageContext<'a>,
     pub config: minijinja::Value,
     #[serde(rename = "$hinoki_cx", serialize_with = "serialize_hinoki_cx")]
     pub hinoki_cx: &'a Arc<HinokiContext>,
 }
 
+/// `page.*` as seen by templates: the content file's metadata, plus its
+/// table of contents once markdown rendering has produced one.
+#[derive(Serialize)]
+pub(crate) struct PageContext<'a> {
+    #[serde(flatten)]
+    pub meta: &'a FileMetadata,
+    #[cfg(feature = "markdown")]
+    pub toc: Vec<TocEntry>,
+}
+
 pub(crate) fn serialize_hinoki_cx<S: Serializer>(
     cx: &Arc<HinokiContext>,
     serializer: S,
@@ -188,16 +473,33 @@ impl MinijinjaStateExt for minijinja::State<'_, '_> {
     }
 }
 
-#[derive(Deserialize)]
+/// Which field to order a directory's files by, requested from a template
+/// via `prev_by`/`next_by`/`sorted_by`, mirroring [`crate::config::SortKey`].
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
-pub(super) enum Ordering {
+pub(super) enum OrderKey {
     Date,
+    Title,
+    Slug,
+    Weight,
+}
+
+/// A parsed `prev_by`/`next_by`/`sorted_by` argument, e.g. `"date"` or
+/// `"-weight"` for descending `weight` order.
+pub(super) struct Ordering {
+    pub(super) key: OrderKey,
+    pub(super) reverse: bool,
 }
 
 impl Ordering {
     pub(super) fn from_string(s: &str) -> Result<Self, minijinja::Error> {
-        Self::deserialize(s.into_deserializer()).map_err(|e: de::value::Error| {
+        let (reverse, key) = match s.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, s),
+        };
+        let key = OrderKey::deserialize(key.into_deserializer()).map_err(|e: de::value::Error| {
             minijinja::Error::new(minijinja::ErrorKind::InvalidOperation, e.to_string())
-        })
+        })?;
+        Ok(Self { key, reverse })
     }
 }
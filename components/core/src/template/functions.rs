@@ -3,7 +3,7 @@
 
 use std::fmt::Display;
 
-use camino::Utf8PathBuf;
+use camino::{Utf8Path, Utf8PathBuf};
 use fs_err as fs;
 use minijinja::{value::Kwargs, ErrorKind, Value};
 
@@ -18,6 +18,8 @@ pub(super) fn get_file(
     kwargs.assert_all_used()?;
 
     let cx = state.hinoki_cx()?;
+    cx.record_dependency(source_dir(&cx).to_owned());
+
     let Some(current_file_idx) = cx.current_file_idx() else {
         return Err(minijinja::Error::new(
             ErrorKind::InvalidOperation,
@@ -51,21 +53,39 @@ fn prev_next_by_impl(
     cx: &HinokiContext,
 ) -> Option<Value> {
     let current_dir_files = cx.current_dir_files();
-    let order_bi_map = cx.get_or_init_file_indices_by(ordering, current_dir_files);
-    let self_idx_ordered = order_bi_map.original_to_ordered[current_file_idx];
+    let order_bi_map = cx.get_or_init_file_indices_by(ordering.key, current_dir_files);
+    let self_rank = order_bi_map.rank(current_file_idx, ordering.reverse);
 
-    let adj_idx = make_adjacent_idx(self_idx_ordered)?;
-    let adj_idx_original = *order_bi_map.ordered_to_original.get(adj_idx)?;
+    let adj_rank = make_adjacent_idx(self_rank)?;
+    let adj_idx_original = order_bi_map.at_rank(adj_rank, ordering.reverse)?;
     Some(Value::from_serialize(&current_dir_files[adj_idx_original]))
 }
 
+/// Returns the current directory's files ordered by `ordering` (e.g.
+/// `"date"`, `"-weight"`), for templates that want a full sorted listing
+/// instead of just the previous/next page via `get_file`.
+pub(super) fn sorted_by(state: &minijinja::State, ordering: &str) -> Result<Value, minijinja::Error> {
+    let cx = state.hinoki_cx()?;
+    let ordering = Ordering::from_string(ordering)?;
+
+    let current_dir_files = cx.current_dir_files();
+    let order_bi_map = cx.get_or_init_file_indices_by(ordering.key, current_dir_files);
+    let sorted = (0..order_bi_map.len())
+        .map(|rank| &current_dir_files[order_bi_map.at_rank(rank, ordering.reverse).unwrap()])
+        .collect::<Vec<_>>();
+    Ok(Value::from_serialize(sorted))
+}
+
 pub(super) fn get_files(
     state: &minijinja::State,
     subdir_name: &str,
 ) -> Result<Value, minijinja::Error> {
     let cx = state.hinoki_cx()?;
     match cx.get_subdir(subdir_name) {
-        Some(subdir_meta) => Ok(Value::from_serialize(subdir_meta.files.get().unwrap())),
+        Some(subdir_meta) => {
+            cx.record_dependency(source_dir(&cx).join(subdir_name));
+            Ok(Value::from_serialize(subdir_meta.files.get().unwrap()))
+        }
         None => Err(minijinja::Error::new(
             minijinja::ErrorKind::InvalidOperation,
             format!("no subdirectory `{subdir_name}`"),
@@ -73,45 +93,241 @@ pub(super) fn get_files(
     }
 }
 
-pub(super) fn load_data(path: String) -> Result<Value, minijinja::Error> {
+/// The directory (relative to the content dir) of the page currently being
+/// rendered.
+fn source_dir(cx: &HinokiContext) -> &Utf8Path {
+    cx.source_path().parent().unwrap_or(Utf8Path::new(""))
+}
+
+/// Returns the `term -> pages` map for a taxonomy declared in
+/// `config.taxonomies`, so a page can list its own terms' sibling pages or
+/// build a tag cloud.
+pub(super) fn get_taxonomy(state: &minijinja::State, name: &str) -> Result<Value, minijinja::Error> {
+    let cx = state.hinoki_cx()?;
+    match cx.taxonomy(name) {
+        Some(terms) => Ok(Value::from_serialize(terms)),
+        None => Err(minijinja::Error::new(
+            ErrorKind::InvalidOperation,
+            format!("no taxonomy `{name}`"),
+        )),
+    }
+}
+
+/// Returns the pages filed under a single term of a taxonomy declared in
+/// `config.taxonomies`, e.g. `get_taxonomy_term("tags", "rust")`.
+pub(super) fn get_taxonomy_term(
+    state: &minijinja::State,
+    name: &str,
+    term: &str,
+) -> Result<Value, minijinja::Error> {
+    let cx = state.hinoki_cx()?;
+    let terms = cx.taxonomy(name).ok_or_else(|| {
+        minijinja::Error::new(ErrorKind::InvalidOperation, format!("no taxonomy `{name}`"))
+    })?;
+
+    match terms.get(term) {
+        Some(pages) => Ok(Value::from_serialize(pages)),
+        None => Err(minijinja::Error::new(
+            ErrorKind::InvalidOperation,
+            format!("no term `{term}` in taxonomy `{name}`"),
+        )),
+    }
+}
+
+/// Returns a hash of a file's contents, for cache-busting a URL (`?h=…`) or
+/// building a Subresource Integrity attribute (`integrity="sha256-…"`). The
+/// file is looked up in the asset directory, then the content directory.
+///
+/// Defaults to a base64-encoded hash, ready to use as-is in `integrity`. Pass
+/// `encoding="hex"` for a hex-encoded hash instead.
+pub(super) fn get_hash(state: &minijinja::State, kwargs: Kwargs) -> Result<Value, minijinja::Error> {
+    let path: String = kwargs.get("path")?;
+    let encoding: Option<String> = kwargs.get("encoding")?;
+    kwargs.assert_all_used()?;
+
+    let cx = state.hinoki_cx()?;
+    let hash = cx
+        .file_hash(Utf8Path::new(&path))
+        .map_err(|e| minijinja::Error::new(ErrorKind::InvalidOperation, e.to_string()))?;
+
+    match encoding.as_deref() {
+        None | Some("base64") => Ok(Value::from(&*hash)),
+        Some("hex") => {
+            use base64::{engine::general_purpose::STANDARD, Engine as _};
+
+            let bytes = STANDARD.decode(&*hash).map_err(|e| {
+                minijinja::Error::new(ErrorKind::InvalidOperation, e.to_string())
+            })?;
+            let hex = bytes.iter().map(|b| format!("{b:02x}")).collect::<String>();
+            Ok(Value::from(hex))
+        }
+        Some(other) => Err(minijinja::Error::new(
+            ErrorKind::InvalidOperation,
+            format!("unsupported encoding `{other}`, expected `base64` or `hex`"),
+        )),
+    }
+}
+
+/// Loads a data file, deserializing it according to `format` (or the file
+/// extension, if `format` isn't given).
+///
+/// Supported formats: `toml`, `json`, `yaml` (or `yml`), `csv`, and `plain`
+/// (returns the raw file contents as a string). CSV files are returned as a
+/// list of records, each keyed by the header row.
+pub(super) fn load_data(
+    state: &minijinja::State,
+    path: String,
+    kwargs: Kwargs,
+) -> Result<Value, minijinja::Error> {
+    let format: Option<String> = kwargs.get("format")?;
+    kwargs.assert_all_used()?;
+
+    let path = Utf8PathBuf::from(path);
+    record_data_dependency(state, &path)?;
+    load_data_file(&path, format.as_deref())
+}
+
+/// Resizes (cropping to fill) the image at `path`, looked up the same way as
+/// [`get_hash`] (asset directory first, then content directory), to
+/// `width`x`height`, re-encodes it as `format` (`jpg`/`jpeg`, `png` or
+/// `webp`) at `quality` (defaults to `80`), and returns the public URL of the
+/// generated, content-addressed asset.
+#[cfg(feature = "images")]
+pub(super) fn resize_image(
+    state: &minijinja::State,
+    path: String,
+    width: u32,
+    height: u32,
+    format: String,
+    kwargs: Kwargs,
+) -> Result<Value, minijinja::Error> {
+    let quality: Option<u8> = kwargs.get("quality")?;
+    kwargs.assert_all_used()?;
+
+    let cx = state.hinoki_cx()?;
+    let url = cx
+        .resize_image(Utf8Path::new(&path), width, height, &format, quality.unwrap_or(80))
+        .map_err(|e| minijinja::Error::new(ErrorKind::InvalidOperation, e.to_string()))?;
+
+    Ok(Value::from(url))
+}
+
+/// Loads every file matching `pattern` (e.g. `"data/authors/*.toml"`),
+/// deserializing each the same way [`load_data`] would, and returns them as
+/// an array ordered deterministically by path.
+///
+/// This lets a directory of one-record-per-file data (authors, projects,
+/// link entries, ...) be iterated over in a template instead of having to be
+/// collected into a single file by hand. If any individual file fails to
+/// load, the error identifies which path it came from.
+pub(super) fn load_data_glob(
+    state: &minijinja::State,
+    pattern: String,
+    kwargs: Kwargs,
+) -> Result<Value, minijinja::Error> {
+    fn make_error(e: impl Display) -> minijinja::Error {
+        minijinja::Error::new(ErrorKind::BadInclude, e.to_string())
+    }
+
+    let format: Option<String> = kwargs.get("format")?;
+    kwargs.assert_all_used()?;
+
+    let mut paths = Vec::new();
+    for entry in glob::glob(&pattern).map_err(make_error)? {
+        let path = entry.map_err(make_error)?;
+        let path = Utf8PathBuf::from_path_buf(path)
+            .map_err(|path| make_error(format!("non-utf8 path `{}`", path.display())))?;
+        paths.push(path);
+    }
+    paths.sort();
+
+    paths
+        .iter()
+        .map(|path| {
+            record_data_dependency(state, path)?;
+            load_data_file(path, format.as_deref()).map_err(|e| make_error(format!("{path}: {e}")))
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .map(Value::from)
+}
+
+// Recorded by canonicalized path so the watcher can match it against
+// filesystem change events regardless of how it was spelled by the caller.
+fn record_data_dependency(state: &minijinja::State, path: &Utf8Path) -> Result<(), minijinja::Error> {
+    if let Ok(canonical) = fs::canonicalize(path)
+        && let Ok(canonical) = Utf8PathBuf::from_path_buf(canonical)
+    {
+        let cx = state.hinoki_cx()?;
+        cx.record_data_dependency(canonical);
+    }
+
+    Ok(())
+}
+
+fn load_data_file(path: &Utf8Path, format: Option<&str>) -> Result<Value, minijinja::Error> {
     // FIXME: MiniJinja's ErrorKind type does not have an Other variant,
     // none of the existing variants really match, update when that changes.
     fn make_error(e: impl Display) -> minijinja::Error {
         minijinja::Error::new(ErrorKind::BadInclude, e.to_string())
     }
 
-    let path = Utf8PathBuf::from(path);
-    let deserialize: fn(&str) -> Result<Value, minijinja::Error> = match path.extension() {
-        Some("toml") => |s| toml::from_str(s).map_err(make_error),
+    let format = match format {
+        Some(format) => format.to_owned(),
+        None => match path.extension() {
+            Some(ext) => ext.to_owned(),
+            None => {
+                return Err(make_error(
+                    "File has no extension; pass an explicit `format` argument",
+                ));
+            }
+        },
+    };
+
+    let file_contents = fs::read_to_string(path).map_err(make_error)?;
+
+    match format.as_str() {
+        "plain" => Ok(Value::from(file_contents)),
+        "toml" => toml::from_str(&file_contents).map_err(make_error),
         #[cfg(feature = "json")]
-        Some("json") => |s| serde_json::from_str(s).map_err(make_error),
+        "json" => serde_json::from_str(&file_contents).map_err(make_error),
         #[cfg(not(feature = "json"))]
-        Some("json") => {
-            return Err(make_error(
-                "hinoki was compiled without support for JSON files.\
-                 Please recompile with the 'json' feature enabled.",
-            ));
-        }
+        "json" => Err(make_error(
+            "hinoki was compiled without support for JSON files.\
+             Please recompile with the 'json' feature enabled.",
+        )),
         #[cfg(feature = "yaml")]
-        Some("yml" | "yaml") => |s| serde_yaml::from_str(s).map_err(make_error),
+        "yml" | "yaml" => serde_yaml::from_str(&file_contents).map_err(make_error),
         #[cfg(not(feature = "yaml"))]
-        Some("yml" | "yaml") => {
-            return Err(make_error(
-                "hinoki was compiled without support for YAML files.\
-                 Please recompile with the 'yaml' feature enabled.",
-            ));
-        }
-        Some(ext) => {
-            return Err(make_error(format!(
-                "Unsupported file extension `{ext}`. \
-                 Only .toml, .json and .yaml / .yml files can be loaded.",
-            )));
-        }
-        None => {
-            return Err(make_error("File must have an extension"));
-        }
-    };
+        "yml" | "yaml" => Err(make_error(
+            "hinoki was compiled without support for YAML files.\
+             Please recompile with the 'yaml' feature enabled.",
+        )),
+        #[cfg(feature = "csv")]
+        "csv" => load_csv(&file_contents).map_err(make_error),
+        #[cfg(not(feature = "csv"))]
+        "csv" => Err(make_error(
+            "hinoki was compiled without support for CSV files.\
+             Please recompile with the 'csv' feature enabled.",
+        )),
+        _ => Err(make_error(format!(
+            "Unsupported format `{format}`. \
+             Supported formats are toml, json, yaml, csv and plain.",
+        ))),
+    }
+}
 
-    let file_contents = fs::read_to_string(path).map_err(make_error)?;
-    deserialize(&file_contents)
+#[cfg(feature = "csv")]
+fn load_csv(contents: &str) -> Result<Value, csv::Error> {
+    let mut reader = csv::ReaderBuilder::new().has_headers(true).from_reader(contents.as_bytes());
+    let headers = reader.headers()?.clone();
+
+    let mut records = Vec::new();
+    for result in reader.records() {
+        let record = result?;
+        let row: indexmap::IndexMap<String, String> =
+            headers.iter().zip(record.iter()).map(|(h, v)| (h.to_owned(), v.to_owned())).collect();
+        records.push(row);
+    }
+
+    Ok(Value::from_serialize(&records))
 }
@@ -0,0 +1,97 @@
+//! Backing cache for the `resize_image` template function: resizes and
+//! re-encodes images on demand, keyed by source path plus transform
+//! parameters, so identical transforms requested concurrently by multiple
+//! pages are only computed once and repeated builds reuse the same output
+//! file.
+
+use std::{
+    collections::HashMap,
+    io::Cursor,
+    sync::{Arc, Mutex, OnceLock},
+};
+
+use camino::Utf8PathBuf;
+use fs_err as fs;
+use image::DynamicImage;
+
+const OUTPUT_SUBDIR: &str = "resized";
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub(super) struct ImageCacheKey {
+    pub(super) path: Utf8PathBuf,
+    pub(super) width: u32,
+    pub(super) height: u32,
+    pub(super) format: String,
+    pub(super) quality: u8,
+}
+
+pub(super) type ImageCache = Mutex<HashMap<ImageCacheKey, Arc<OnceLock<Result<String, String>>>>>;
+
+/// Resizes (cropping to fill) the image at `source_path` to `width`x`height`,
+/// re-encodes it as `format` at `quality`, and writes it under
+/// `output_dir/resized` using a filename derived from `key`, reusing the
+/// cached result (or in-progress computation) for an identical `key` if one
+/// exists.
+///
+/// The output filename is content-addressed (derived from the transform
+/// parameters, not a counter), so it never collides with regular page
+/// output and can be written directly instead of through
+/// [`OutputDirManager::output_path`][crate::build::OutputDirManager::output_path].
+pub(super) fn resize(
+    cache: &ImageCache,
+    key: ImageCacheKey,
+    source_path: &camino::Utf8Path,
+    output_dir: &camino::Utf8Path,
+) -> anyhow::Result<String> {
+    let slot = {
+        let mut cache = cache.lock().unwrap();
+        cache.entry(key.clone()).or_insert_with(|| Arc::new(OnceLock::new())).clone()
+    };
+
+    slot.get_or_init(|| resize_uncached(&key, source_path, output_dir).map_err(|e| e.to_string()))
+        .clone()
+        .map_err(anyhow::Error::msg)
+}
+
+fn resize_uncached(
+    key: &ImageCacheKey,
+    source_path: &camino::Utf8Path,
+    output_dir: &camino::Utf8Path,
+) -> anyhow::Result<String> {
+    let file_name = format!("{}.{}", crate::util::content_hash(key_bytes(key)), key.format);
+    let output_rel_path = camino::Utf8Path::new(OUTPUT_SUBDIR).join(&file_name);
+    let output_path = output_dir.join(&output_rel_path);
+
+    if !output_path.is_file() {
+        let image = image::open(source_path)
+            .map_err(|e| anyhow::anyhow!("opening image `{source_path}`: {e}"))?;
+        let resized =
+            image.resize_to_fill(key.width, key.height, image::imageops::FilterType::Lanczos3);
+        let encoded = encode(&resized, &key.format, key.quality)?;
+
+        fs::create_dir_all(output_dir.join(OUTPUT_SUBDIR))?;
+        fs::write(&output_path, encoded)?;
+    }
+
+    Ok(format!("/{output_rel_path}"))
+}
+
+fn encode(image: &DynamicImage, format: &str, quality: u8) -> anyhow::Result<Vec<u8>> {
+    let mut buf = Vec::new();
+    match format {
+        "jpg" | "jpeg" => {
+            let encoder = image::codecs::jpeg::JpegEncoder::new_with_quality(&mut buf, quality);
+            image.write_with_encoder(encoder)?;
+        }
+        "png" => image.write_to(&mut Cursor::new(&mut buf), image::ImageFormat::Png)?,
+        "webp" => image.write_to(&mut Cursor::new(&mut buf), image::ImageFormat::WebP)?,
+        other => anyhow::bail!("unsupported image output format `{other}`"),
+    }
+
+    Ok(buf)
+}
+
+fn key_bytes(key: &ImageCacheKey) -> Vec<u8> {
+    format!("{}\0{}\0{}\0{}\0{}", key.path, key.width, key.height, key.format, key.quality)
+        .into_bytes()
+}
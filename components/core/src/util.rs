@@ -5,6 +5,13 @@ use itertools::Itertools as _;
 use serde::{Deserialize, Serialize};
 use toml::value::{Date, Offset, Time};
 
+/// Hashes arbitrary bytes for incremental-build change detection (content
+/// files, templates, config files). Not a cryptographic use case; blake3 is
+/// chosen purely for speed.
+pub(crate) fn content_hash(bytes: impl AsRef<[u8]>) -> String {
+    blake3::hash(bytes.as_ref()).to_hex().to_string()
+}
+
 #[derive(Debug)]
 pub(crate) struct OrderBiMap {
     pub ordered_to_original: Vec<usize>,
@@ -27,6 +34,24 @@ impl OrderBiMap {
 
         Self { ordered_to_original, original_to_ordered }
     }
+
+    pub(crate) fn len(&self) -> usize {
+        self.ordered_to_original.len()
+    }
+
+    /// Where `original_idx` falls in the ordering, counting from the end
+    /// instead of the start if `reverse`.
+    pub(crate) fn rank(&self, original_idx: usize, reverse: bool) -> usize {
+        let rank = self.original_to_ordered[original_idx];
+        if reverse { self.len() - 1 - rank } else { rank }
+    }
+
+    /// The original index at `rank`, counting from the end instead of the
+    /// start if `reverse`. `None` if `rank` is out of bounds.
+    pub(crate) fn at_rank(&self, rank: usize, reverse: bool) -> Option<usize> {
+        let rank = if reverse { self.len().checked_sub(1)?.checked_sub(rank)? } else { rank };
+        self.ordered_to_original.get(rank).copied()
+    }
 }
 
 /// Like toml::value::Datetime, but with the date being required.
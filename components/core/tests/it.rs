@@ -39,9 +39,11 @@ fn run_test(name: &str, include_drafts: bool) {
     let tests_dir = Utf8Path::new(env!("CARGO_MANIFEST_DIR")).join("tests");
 
     let mut config = read_config(&tests_dir.join(name).join("config.toml")).unwrap();
-    config.set_output_dir(Utf8Path::from_path(temp_output_dir).unwrap().to_owned());
+    let temp_output_dir_utf8 = Utf8Path::from_path(temp_output_dir).unwrap().to_owned();
+    config.set_output_dir(temp_output_dir_utf8.clone());
+    config.set_cache_dir(temp_output_dir_utf8.join(".hinoki-cache"));
 
-    build(config, include_drafts);
+    build(config, include_drafts, false);
 
     let expected_root = tests_dir.join(format!("{name}.out"));
     let mut expected_iter = WalkDir::new(&expected_root).sort_by_file_name().into_iter();
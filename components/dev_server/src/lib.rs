@@ -1,4 +1,5 @@
 use std::{
+    convert::Infallible,
     fmt,
     net::{Ipv6Addr, SocketAddr},
     process::ExitCode,
@@ -6,18 +7,28 @@ use std::{
     time::{Duration, Instant},
 };
 
-use camino::Utf8Path;
-use fs_err as fs;
+use bytes::Bytes;
+use camino::{Utf8Path, Utf8PathBuf};
 use hinoki_cli::ServeArgs;
-use hinoki_core::{
-    build::Build,
-    config::{Config, Inputs},
-};
+use hinoki_core::{build::Build, config::Config};
+use http_body_util::Full;
+use hyper::{body::Incoming, Request, Response};
 use hyper_util::service::TowerToHyperService;
 use tempfile::tempdir;
+use tokio::sync::broadcast;
+use tower::{Layer as _, ServiceExt as _};
 use tower_http::services::ServeDir;
 use tracing::{error, info};
 
+mod reload;
+mod watch;
+
+use self::reload::{InjectLiveReloadLayer, ReloadMessage};
+
+/// How many pending reload notifications a slow client can fall behind by
+/// before older ones are dropped in favor of the latest build state.
+const RELOAD_CHANNEL_CAPACITY: usize = 16;
+
 pub fn run(config: Config, args: ServeArgs) -> ExitCode {
     let res = tokio::runtime::Builder::new_multi_thread()
         .enable_all()
@@ -36,104 +47,26 @@ pub fn run(config: Config, args: ServeArgs) -> ExitCode {
 
 async fn run_inner(mut config: Config, args: ServeArgs) -> anyhow::Result<()> {
     let output_dir = tempdir()?;
-    config.set_output_dir(output_dir.path().to_owned().try_into()?);
+    let output_dir_path: Utf8PathBuf = output_dir.path().to_owned().try_into()?;
+    config.set_output_dir(output_dir_path.to_owned());
+    config.set_cache_dir(output_dir_path.join(".hinoki-cache"));
 
-    let build = Build::new(config, true);
+    // The dev server builds into a fresh temporary directory every run, so
+    // there's never a previous build's cache worth consulting.
+    let build = Build::new(config, true, true);
     let begin = Instant::now();
     build.run();
     info!("Built site in {}", FormatDuration(begin.elapsed()));
 
     let config = build.config().clone();
-    let _watch_guard = start_watch(build)?;
-    serve(&config, args).await?;
+    let (reload_tx, _) = broadcast::channel(RELOAD_CHANNEL_CAPACITY);
+    let _watch_guard = watch::start(build, reload_tx.clone())?;
+    serve(&config, args, reload_tx).await?;
 
     Ok(())
 }
 
-/// Start file notification watcher.
-///
-/// Dropping the returned value stops the watcher thread.
-fn start_watch(build: Build) -> anyhow::Result<impl Drop> {
-    use notify::{
-        EventKind, RecursiveMode,
-        event::{CreateKind, ModifyKind},
-    };
-    use notify_debouncer_full::{DebounceEventResult, new_debouncer};
-
-    const DEBOUNCE_DURATION: Duration = Duration::from_millis(100);
-
-    #[rustfmt::skip] // buggy, can remove when Inputs gets another field
-    let Inputs {
-        mut project_root,
-        config_file,
-        content_dir,
-        asset_dir,
-        template_dir,
-        sublime_dir,
-    } = build.config().inputs();
-
-    if project_root == "" {
-        // If the config path is only a filename, `parent()` returns an empty path.
-        // We can't pass that to `fs::canonicalize`.
-        project_root = ".".into()
-    }
-    let project_root_canon = fs::canonicalize(project_root)?;
-
-    let mut debouncer = new_debouncer(DEBOUNCE_DURATION, None, {
-        let project_root_canon = project_root_canon.clone();
-        move |res: DebounceEventResult| match res {
-            Err(errors) => {
-                for error in errors {
-                    error!("notify error: {error}");
-                }
-            }
-            Ok(mut events) => {
-                events.retain_mut(|ev| {
-                    match &ev.kind {
-                        EventKind::Access(_)
-                        | EventKind::Create(CreateKind::Folder)
-                        | EventKind::Modify(ModifyKind::Metadata(_)) => return false,
-                        EventKind::Any
-                        | EventKind::Create(_)
-                        | EventKind::Modify(_)
-                        | EventKind::Remove(_)
-                        | EventKind::Other => {}
-                    };
-
-                    ev.paths.retain(|path| {
-                        let rel_path = match path.strip_prefix(&project_root_canon) {
-                            Ok(p) => p,
-                            Err(e) => {
-                                error!("notify event path error: {e}");
-                                return false;
-                            }
-                        };
-
-                        *rel_path.as_os_str() == *config_file
-                            || rel_path.starts_with(&content_dir)
-                            || rel_path.starts_with(&asset_dir)
-                            || rel_path.starts_with(&template_dir)
-                            || rel_path.starts_with(&sublime_dir)
-                    });
-
-                    !ev.paths.is_empty()
-                });
-
-                if !events.is_empty() {
-                    let begin = Instant::now();
-                    build.run();
-                    info!("Rebuilt site in {}", FormatDuration(begin.elapsed()));
-                }
-            }
-        }
-    })?;
-
-    debouncer.watch(project_root_canon, RecursiveMode::Recursive)?;
-
-    Ok(debouncer)
-}
-
-async fn serve(config: &Config, args: ServeArgs) -> anyhow::Result<()> {
+async fn serve(config: &Config, args: ServeArgs, reload_tx: broadcast::Sender<ReloadMessage>) -> anyhow::Result<()> {
     let url = format!("http://localhost:{}", args.port);
     info!("Starting development server on {url}");
 
@@ -151,13 +84,16 @@ async fn serve(config: &Config, args: ServeArgs) -> anyhow::Result<()> {
         let (socket, _remote_addr) = listener.accept().await?;
 
         let output_dir = Arc::clone(&output_dir);
+        let reload_tx = reload_tx.clone();
         tokio::spawn(async move {
             let socket = hyper_util::rt::TokioIo::new(socket);
-            let service = TowerToHyperService::new(ServeDir::new(&*output_dir));
+            let service = TowerToHyperService::new(tower::service_fn(move |req| {
+                handle_request(req, Arc::clone(&output_dir), reload_tx.clone())
+            }));
 
             if let Err(err) =
                 hyper_util::server::conn::auto::Builder::new(hyper_util::rt::TokioExecutor::new())
-                    .serve_connection(socket, service)
+                    .serve_connection_with_upgrades(socket, service)
                     .await
             {
                 error!("Failed to serve connection: {err:#}");
@@ -166,6 +102,36 @@ async fn serve(config: &Config, args: ServeArgs) -> anyhow::Result<()> {
     }
 }
 
+/// Serves a single request: upgrades live-reload websocket requests, and
+/// otherwise serves the built site, injecting the live-reload client script
+/// into HTML responses.
+async fn handle_request(
+    mut req: Request<Incoming>,
+    output_dir: Arc<Utf8Path>,
+    reload_tx: broadcast::Sender<ReloadMessage>,
+) -> Result<Response<Full<Bytes>>, Infallible> {
+    if req.uri().path() == reload::LIVERELOAD_PATH && hyper_tungstenite::is_upgrade_request(&req) {
+        return match hyper_tungstenite::upgrade(&mut req, None) {
+            Ok((response, websocket)) => {
+                tokio::spawn(reload::handle_connection(websocket, reload_tx.subscribe()));
+                Ok(response.map(|_| Full::new(Bytes::new())))
+            }
+            Err(e) => {
+                error!("live-reload upgrade failed: {e:#}");
+                Ok(Response::builder().status(400).body(Full::new(Bytes::new())).unwrap())
+            }
+        };
+    }
+
+    // `.precompressed_gzip()` and `.precompressed_br()` serve a build's
+    // `.gz`/`.br` siblings (see `build::precompress`) when present and
+    // accepted by the client, falling back to the uncompressed file
+    // otherwise; a no-op for builds that didn't precompress anything.
+    let serve_dir = ServeDir::new(&*output_dir).precompressed_gzip().precompressed_br();
+    let Ok(response) = InjectLiveReloadLayer.layer(serve_dir).oneshot(req).await;
+    Ok(response)
+}
+
 struct FormatDuration(Duration);
 
 impl fmt::Display for FormatDuration {
@@ -0,0 +1,180 @@
+use std::{
+    convert::Infallible,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use bytes::Bytes;
+use futures_util::{SinkExt, StreamExt};
+use http_body_util::{BodyExt as _, Full};
+use hyper::{body::Incoming, Request, Response};
+use hyper_tungstenite::{tungstenite::Message, HyperWebsocket};
+use tokio::sync::broadcast;
+use tower::{Layer, Service, ServiceExt as _};
+use tower_http::services::ServeDir;
+use tracing::{error, warn};
+
+/// Path the injected client script connects to for live-reload notifications.
+pub(crate) const LIVERELOAD_PATH: &str = "/__hinoki_livereload";
+
+/// Sent to every connected client after each rebuild attempt.
+#[derive(Clone, Debug)]
+pub(crate) enum ReloadMessage {
+    /// The rebuild succeeded; the client should reload the page.
+    Reloaded,
+    /// The rebuild failed; the client should keep showing the old page but
+    /// report the failure.
+    BuildFailed,
+}
+
+impl ReloadMessage {
+    fn as_json(&self) -> &'static str {
+        match self {
+            ReloadMessage::Reloaded => r#"{"type":"reload"}"#,
+            ReloadMessage::BuildFailed => {
+                r#"{"type":"error","message":"build failed, check the terminal for details"}"#
+            }
+        }
+    }
+}
+
+/// Inlined into every served HTML page. Connects back to [`LIVERELOAD_PATH`]
+/// and reloads the page (or logs a build error) whenever a message arrives.
+const CLIENT_SCRIPT_TEMPLATE: &str = r#"<script>
+(function () {
+    function connect() {
+        const ws = new WebSocket("ws://" + location.host + "__LIVERELOAD_PATH__");
+        ws.onmessage = (event) => {
+            const message = JSON.parse(event.data);
+            if (message.type === "reload") {
+                location.reload();
+            } else if (message.type === "error") {
+                console.error("hinoki build error:", message.message);
+            }
+        };
+        ws.onclose = () => setTimeout(connect, 1000);
+    }
+    connect();
+})();
+</script>"#;
+
+/// Splices the live-reload client script just before `</body>`, or appends it
+/// if the document has no closing body tag.
+pub(crate) fn inject_script(html: Bytes) -> Bytes {
+    let html = String::from_utf8_lossy(&html);
+    let script = CLIENT_SCRIPT_TEMPLATE.replace("__LIVERELOAD_PATH__", LIVERELOAD_PATH);
+
+    let with_script = match html.rfind("</body>") {
+        Some(idx) => {
+            let mut out = String::with_capacity(html.len() + script.len());
+            out.push_str(&html[..idx]);
+            out.push_str(&script);
+            out.push_str(&html[idx..]);
+            out
+        }
+        None => format!("{html}{script}"),
+    };
+
+    Bytes::from(with_script)
+}
+
+/// [`tower::Layer`] that wraps a [`ServeDir`], injecting the live-reload
+/// client script into its HTML responses.
+#[derive(Clone)]
+pub(crate) struct InjectLiveReloadLayer;
+
+impl Layer<ServeDir> for InjectLiveReloadLayer {
+    type Service = InjectLiveReload;
+
+    fn layer(&self, inner: ServeDir) -> Self::Service {
+        InjectLiveReload { inner }
+    }
+}
+
+/// Serves files via the wrapped [`ServeDir`], splicing [`inject_script`]
+/// into any `text/html` response before it reaches the client.
+#[derive(Clone)]
+pub(crate) struct InjectLiveReload {
+    inner: ServeDir,
+}
+
+impl Service<Request<Incoming>> for InjectLiveReload {
+    type Response = Response<Full<Bytes>>;
+    type Error = Infallible;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<Incoming>) -> Self::Future {
+        let inner = self.inner.clone();
+        Box::pin(async move {
+            // `ServeDir`'s `Service::Error` is `Infallible`.
+            let Ok(response) = inner.oneshot(req).await;
+
+            let is_html = response
+                .headers()
+                .get(hyper::header::CONTENT_TYPE)
+                .is_some_and(|v| v.as_bytes().starts_with(b"text/html"));
+
+            if !is_html {
+                let (parts, body) = response.into_parts();
+                let body = body.collect().await.map(|c| c.to_bytes()).unwrap_or_default();
+                return Ok(Response::from_parts(parts, Full::new(body)));
+            }
+
+            let (mut parts, body) = response.into_parts();
+            let body = body.collect().await.map(|c| c.to_bytes()).unwrap_or_default();
+            let body = inject_script(body);
+            parts.headers.remove(hyper::header::CONTENT_LENGTH);
+
+            Ok(Response::from_parts(parts, Full::new(body)))
+        })
+    }
+}
+
+/// Drives a single live-reload websocket connection: forwards every message
+/// broadcast from the watcher to the client, until the client disconnects.
+pub(crate) async fn handle_connection(
+    websocket: HyperWebsocket,
+    mut reload_rx: broadcast::Receiver<ReloadMessage>,
+) {
+    let mut websocket = match websocket.await {
+        Ok(websocket) => websocket,
+        Err(e) => {
+            error!("live-reload websocket handshake failed: {e}");
+            return;
+        }
+    };
+
+    loop {
+        tokio::select! {
+            message = reload_rx.recv() => {
+                let message = match message {
+                    Ok(message) => message,
+                    // A burst of rebuilds filled the channel before we could
+                    // forward them all; the client only cares about the
+                    // latest state anyway.
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+
+                if websocket.send(Message::text(message.as_json())).await.is_err() {
+                    break;
+                }
+            }
+            incoming = websocket.next() => {
+                match incoming {
+                    Some(Ok(Message::Close(_))) | None => break,
+                    Some(Err(e)) => {
+                        warn!("live-reload websocket error: {e}");
+                        break;
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,172 @@
+use std::{
+    collections::HashSet,
+    time::{Duration, Instant},
+};
+
+use camino::Utf8Path;
+use fs_err as fs;
+use hinoki_core::{build::Build, config::Inputs};
+use notify::{
+    event::{CreateKind, ModifyKind},
+    EventKind, RecursiveMode,
+};
+use notify_debouncer_full::{new_debouncer, DebounceEventResult};
+use tokio::sync::broadcast;
+use tracing::{error, info};
+
+use crate::{reload::ReloadMessage, FormatDuration};
+
+const DEBOUNCE_DURATION: Duration = Duration::from_millis(100);
+
+/// Start the filesystem watcher, rebuilding and notifying `reload_tx` on
+/// every relevant change.
+///
+/// Dropping the returned value stops the watcher thread.
+pub(crate) fn start(build: Build, reload_tx: broadcast::Sender<ReloadMessage>) -> anyhow::Result<impl Drop> {
+    #[rustfmt::skip] // buggy, can remove when Inputs gets another field
+    let Inputs {
+        mut project_root,
+        config_file,
+        include_files,
+        content_dir,
+        asset_dir,
+        template_dir,
+        sublime_dir,
+    } = build.config().inputs();
+
+    if project_root == "" {
+        // If the config path is only a filename, `parent()` returns an empty path.
+        // We can't pass that to `fs::canonicalize`.
+        project_root = ".".into()
+    }
+    let project_root_canon = fs::canonicalize(project_root)?;
+    // Already canonicalized by `read_config`; may live outside
+    // `project_root_canon` (e.g. a shared base config in a parent directory),
+    // in which case it's outside the watched tree and changes to it won't be
+    // picked up, same as any other out-of-tree input.
+    let include_files: HashSet<_> = include_files.into_iter().collect();
+
+    let mut debouncer = new_debouncer(DEBOUNCE_DURATION, None, {
+        let project_root_canon = project_root_canon.clone();
+        move |res: DebounceEventResult| match res {
+            Err(errors) => {
+                for error in errors {
+                    error!("notify error: {error}");
+                }
+            }
+            Ok(mut events) => {
+                // Snapshotted once per batch: paths read via `load_data` in
+                // the most recent build, so a change to one can be mapped
+                // back to the pages that depend on it instead of forcing a
+                // full rebuild.
+                let data_file_paths = build.data_file_paths();
+
+                events.retain_mut(|ev| {
+                    match &ev.kind {
+                        EventKind::Access(_)
+                        | EventKind::Create(CreateKind::Folder)
+                        | EventKind::Modify(ModifyKind::Metadata(_)) => return false,
+                        EventKind::Any
+                        | EventKind::Create(_)
+                        | EventKind::Modify(_)
+                        | EventKind::Remove(_)
+                        | EventKind::Other => {}
+                    };
+
+                    ev.paths.retain(|path| {
+                        if let Some(p) = Utf8Path::from_path(path)
+                            && (include_files.contains(p) || data_file_paths.contains(p))
+                        {
+                            return true;
+                        }
+
+                        let rel_path = match path.strip_prefix(&project_root_canon) {
+                            Ok(p) => p,
+                            Err(e) => {
+                                error!("notify event path error: {e}");
+                                return false;
+                            }
+                        };
+
+                        *rel_path.as_os_str() == *config_file
+                            || rel_path.starts_with(&content_dir)
+                            || rel_path.starts_with(&asset_dir)
+                            || rel_path.starts_with(&template_dir)
+                            || rel_path.starts_with(&sublime_dir)
+                    });
+
+                    !ev.paths.is_empty()
+                });
+
+                if !events.is_empty() {
+                    // If every surviving change is inside the content dir (or
+                    // is a tracked `load_data` file), we can ask for an
+                    // incremental rebuild of just the affected pages.
+                    // Otherwise (a template, asset, or the config file
+                    // changed) fall back to a full rebuild, since those can
+                    // affect any number of pages in ways we don't track.
+                    let mut changed_content_paths = HashSet::new();
+                    let mut changed_data_paths = HashSet::new();
+                    let mut needs_full_rebuild = false;
+                    'events: for ev in &events {
+                        for path in &ev.paths {
+                            if let Some(p) = Utf8Path::from_path(path)
+                                && data_file_paths.contains(p)
+                            {
+                                changed_data_paths.insert(p.to_owned());
+                                continue;
+                            }
+
+                            // An out-of-tree include file (kept by the
+                            // retain above despite not being under
+                            // `project_root_canon`) can't be mapped to a
+                            // content-relative path; fall back to a full
+                            // rebuild.
+                            let Ok(rel_path) = path.strip_prefix(&project_root_canon) else {
+                                needs_full_rebuild = true;
+                                break 'events;
+                            };
+                            let Some(rel_path) = Utf8Path::from_path(rel_path) else {
+                                // Non-utf8 paths aren't supported elsewhere in
+                                // hinoki either; play it safe and rebuild
+                                // everything rather than silently ignoring it.
+                                needs_full_rebuild = true;
+                                break 'events;
+                            };
+
+                            match rel_path.strip_prefix(&content_dir) {
+                                Ok(content_rel_path) => {
+                                    changed_content_paths.insert(content_rel_path.to_owned());
+                                }
+                                Err(_) => {
+                                    needs_full_rebuild = true;
+                                    break 'events;
+                                }
+                            }
+                        }
+                    }
+
+                    let begin = Instant::now();
+                    let success = if needs_full_rebuild {
+                        build.run_reporting_success()
+                    } else {
+                        build.rebuild_changed(&changed_content_paths, &changed_data_paths)
+                    };
+                    info!("Rebuilt site in {}", FormatDuration(begin.elapsed()));
+
+                    // No receivers just means no browser tab is currently
+                    // connected; that's not an error.
+                    let _ = reload_tx.send(if success {
+                        ReloadMessage::Reloaded
+                    } else {
+                        ReloadMessage::BuildFailed
+                    });
+                }
+            }
+        }
+    })?;
+
+    debouncer.watch(project_root_canon, RecursiveMode::Recursive)?;
+
+    Ok(debouncer)
+}
@@ -2,7 +2,7 @@ use std::process::ExitCode;
 
 use clap::Parser as _;
 use hinoki_core::{
-    build::{build, dump},
+    build::{self, build, dump},
     read_config,
 };
 use tracing::error;
@@ -29,8 +29,8 @@ fn main() -> ExitCode {
     };
 
     match args.command {
-        Command::Build(args) => build(config, args.include_drafts),
-        Command::DumpMetadata => dump(config),
+        Command::Build(args) => build(config, args.include_drafts, args.force),
+        Command::DumpMetadata(args) => dump(config, convert_dump_format(args.format)),
         #[cfg(feature = "dev-server")]
         Command::Serve(args) => hinoki_dev_server::run(config, args),
         #[cfg(not(feature = "dev-server"))]
@@ -41,5 +41,31 @@ fn main() -> ExitCode {
             );
             ExitCode::FAILURE
         }
+        #[cfg(feature = "syntax-highlighting")]
+        Command::SyntectToCss(args) => match hinoki_core::syntect_to_css(&config, &args.theme) {
+            Ok(css) => {
+                println!("{css}");
+                ExitCode::SUCCESS
+            }
+            Err(e) => {
+                error!("{e:#}");
+                ExitCode::FAILURE
+            }
+        },
+        #[cfg(not(feature = "syntax-highlighting"))]
+        Command::SyntectToCss(_) => {
+            error!(
+                "hinoki was compiled without support for this command.\
+                 Please recompile with the 'syntax-highlighting' feature enabled."
+            );
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn convert_dump_format(format: hinoki_cli::DumpFormat) -> build::DumpFormat {
+    match format {
+        hinoki_cli::DumpFormat::Debug => build::DumpFormat::Debug,
+        hinoki_cli::DumpFormat::Json => build::DumpFormat::Json,
     }
 }